@@ -1,6 +1,5 @@
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use neocash::completion::ShellCompleter;
-use std::path::PathBuf;
 
 fn bench_command_completion(c: &mut Criterion) {
     let completer = ShellCompleter::new();
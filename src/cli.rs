@@ -24,4 +24,48 @@ pub struct Args {
     /// Do not load or save command history
     #[arg(short, long)]
     pub no_history: bool,
+
+    /// Act as a login shell and run the login profile, as when `argv[0]` starts with `-`
+    #[arg(short, long)]
+    pub login: bool,
+
+    /// Do not run the startup script of an interactive shell
+    #[arg(long)]
+    pub norc: bool,
+
+    /// Run the given startup script instead of ~/.config/neocash/init.ncash
+    #[arg(long, value_name = "FILE", conflicts_with = "norc")]
+    pub rcfile: Option<PathBuf>,
+
+    /// Exit when a command fails (`set -e`)
+    #[arg(short = 'e')]
+    pub errexit: bool,
+
+    /// Treat unset variables as an error (`set -u`)
+    #[arg(short = 'u')]
+    pub nounset: bool,
+
+    /// Print commands before they run (`set -x`)
+    #[arg(short = 'x')]
+    pub xtrace: bool,
+
+    /// Disable pathname expansion (`set -f`)
+    #[arg(short = 'f')]
+    pub noglob: bool,
+
+    /// Do not overwrite existing files with `>` (`set -C`)
+    #[arg(short = 'C')]
+    pub noclobber: bool,
+
+    /// Turn on a shell option by name, like `-o pipefail`
+    #[arg(short = 'o', value_name = "OPTION")]
+    pub options: Vec<String>,
+
+    /// Execute the commands in the given string instead of reading them
+    #[arg(short = 'c', value_name = "COMMANDS")]
+    pub command: Option<String>,
+
+    /// Script to run and its arguments, or `$0` and the arguments with `-c`
+    #[arg(trailing_var_arg = true)]
+    pub args: Vec<String>,
 }
@@ -0,0 +1,129 @@
+// commands/alias.rs
+
+use crate::commands::FnBuiltin;
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io};
+use std::io::Write;
+
+pub(super) const BUILTINS: &[FnBuiltin] = &[
+    FnBuiltin::new(
+        "alias",
+        "alias [name[=value]...]",
+        "Defines aliases, or prints them. Aliases are expanded when a command line is read.",
+        alias,
+    ),
+    FnBuiltin::new(
+        "unalias",
+        "unalias -a | name...",
+        "Removes aliases; -a removes all of them.",
+        unalias,
+    ),
+    FnBuiltin::new(
+        "abbr",
+        "abbr [-a | -e | -l | -s] [name [expansion...]]",
+        "Manages abbreviations, which the line editor expands as they are typed.\n\
+         -a adds one (the default with arguments), -e erases, -l lists the names\n\
+         and -s shows them as commands (the default without arguments).",
+        abbr,
+    ),
+];
+
+fn alias(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let quoted =
+        |name: &str, value: &str| format!("alias {}='{}'", name, value.replace('\'', r"'\''"));
+    let mut out = io.stdout();
+
+    if args.len() == 1 {
+        let mut aliases: Vec<_> = executor.state.aliases.iter().collect();
+        aliases.sort();
+        for (name, value) in aliases {
+            writeln!(out, "{}", quoted(name, value))?;
+        }
+        return Ok(0);
+    }
+
+    let mut status = 0;
+    for arg in &args[1..] {
+        match arg.split_once('=') {
+            Some((name, value)) if !name.is_empty() => {
+                executor
+                    .state
+                    .aliases
+                    .insert(name.to_string(), value.to_string());
+            }
+            _ => match executor.state.aliases.get(arg) {
+                Some(value) => {
+                    writeln!(out, "{}", quoted(arg, value))?;
+                }
+                None => {
+                    executor.report(io, &format!("{}: not found", arg).into(), Some("alias"));
+                    status = 1;
+                }
+            },
+        }
+    }
+    Ok(status)
+}
+
+fn unalias(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    if args.get(1).map(String::as_str) == Some("-a") {
+        executor.state.aliases.clear();
+        return Ok(0);
+    }
+    for name in &args[1..] {
+        if executor.state.aliases.remove(name).is_none() {
+            return Err(NeocashError::invalid(name, "not found"));
+        }
+    }
+    Ok(0)
+}
+
+fn abbr(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let (flag, args) = match args.get(1).map(String::as_str) {
+        Some(flag) if flag.starts_with('-') => (flag, &args[2..]),
+        None => ("--show", &args[1..]),
+        _ => ("--add", &args[1..]),
+    };
+    let mut out = io.stdout();
+
+    match flag {
+        "-a" | "--add" => {
+            let (name, expansion) = args
+                .split_first()
+                .filter(|(_, expansion)| !expansion.is_empty())
+                .ok_or_else(|| {
+                    NeocashError::Usage("usage: abbr --add name expansion...".to_string())
+                })?;
+            if name.contains(char::is_whitespace) {
+                return Err(NeocashError::invalid(
+                    name,
+                    "abbreviation cannot contain spaces",
+                ));
+            }
+            executor
+                .state
+                .abbreviations
+                .insert(name.clone(), expansion.join(" "));
+        }
+        "-e" | "--erase" => {
+            for name in args {
+                if executor.state.abbreviations.remove(name).is_none() {
+                    return Err(NeocashError::invalid(name, "not found"));
+                }
+            }
+        }
+        "-l" | "--list" | "-s" | "--show" => {
+            let mut abbreviations: Vec<_> = executor.state.abbreviations.iter().collect();
+            abbreviations.sort();
+            for (name, expansion) in abbreviations {
+                let line = match flag {
+                    "-l" | "--list" => name.clone(),
+                    _ => format!("abbr -a -- {} '{}'", name, expansion.replace('\'', r"'\''")),
+                };
+                writeln!(out, "{}", line)?;
+            }
+        }
+        flag => return Err(NeocashError::Usage(format!("{}: invalid option", flag))),
+    }
+    Ok(0)
+}
@@ -0,0 +1,156 @@
+// commands/builtin.rs
+
+use crate::commands::{alias, control, jobs, shell, vars};
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A command run by the shell itself. Builtins live in the [`Builtins`]
+/// registry of an [`Executor`], where embedders can add their own.
+pub trait Builtin {
+    /// Name the builtin is invoked by
+    fn name(&self) -> &str;
+
+    /// Synopsis, like `cd [dir]`
+    fn usage(&self) -> &str;
+
+    /// Description shown by `help name`
+    fn help(&self) -> &str;
+
+    /// Candidates for the word after `args`, where `args[0]` is the name.
+    /// Files are completed when there are none.
+    fn complete(&self, _args: &[String]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Runs the builtin with `args[0]` set to its name. Errors are reported
+    /// by the shell as `neocash: name: error` with the status of the error.
+    fn execute(
+        &self,
+        executor: &mut Executor,
+        args: &[String],
+        io: &Io,
+    ) -> Result<i32, NeocashError>;
+}
+
+type Run = fn(&mut Executor, &[String], &Io) -> Result<i32, NeocashError>;
+type Complete = fn(&[String]) -> Vec<String>;
+
+/// A builtin implemented by plain functions, which is how the standard
+/// builtins are defined
+#[derive(Clone, Copy)]
+pub struct FnBuiltin {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    run: Run,
+    complete: Option<Complete>,
+}
+
+impl FnBuiltin {
+    pub const fn new(
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        run: Run,
+    ) -> Self {
+        Self {
+            name,
+            usage,
+            help,
+            run,
+            complete: None,
+        }
+    }
+
+    /// Completes the arguments with `complete`
+    pub const fn with_completion(self, complete: Complete) -> Self {
+        Self {
+            complete: Some(complete),
+            ..self
+        }
+    }
+}
+
+impl Builtin for FnBuiltin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn usage(&self) -> &str {
+        self.usage
+    }
+
+    fn help(&self) -> &str {
+        self.help
+    }
+
+    fn complete(&self, args: &[String]) -> Vec<String> {
+        self.complete
+            .map(|complete| complete(args))
+            .unwrap_or_default()
+    }
+
+    fn execute(
+        &self,
+        executor: &mut Executor,
+        args: &[String],
+        io: &Io,
+    ) -> Result<i32, NeocashError> {
+        (self.run)(executor, args, io)
+    }
+}
+
+/// Builtins by name
+#[derive(Clone)]
+pub struct Builtins {
+    table: HashMap<String, Rc<dyn Builtin>>,
+}
+
+impl Default for Builtins {
+    /// The standard builtins
+    fn default() -> Self {
+        let mut builtins = Self {
+            table: HashMap::new(),
+        };
+        let standard = [
+            alias::BUILTINS,
+            control::BUILTINS,
+            jobs::BUILTINS,
+            shell::BUILTINS,
+            vars::BUILTINS,
+        ];
+        for builtin in standard.into_iter().flatten() {
+            builtins.register(*builtin);
+        }
+        builtins
+    }
+}
+
+impl Builtins {
+    /// Adds a builtin, replacing any with the same name
+    pub fn register(&mut self, builtin: impl Builtin + 'static) {
+        self.table
+            .insert(builtin.name().to_string(), Rc::new(builtin));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rc<dyn Builtin>> {
+        self.table.get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.table.contains_key(name)
+    }
+
+    /// Every builtin, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = &Rc<dyn Builtin>> {
+        let mut builtins: Vec<_> = self.table.values().collect();
+        builtins.sort_by(|a, b| a.name().cmp(b.name()));
+        builtins.into_iter()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|builtin| builtin.name())
+    }
+}
@@ -0,0 +1,167 @@
+// commands/control.rs
+
+use crate::commands::FnBuiltin;
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io, LoopControl};
+
+pub(super) const BUILTINS: &[FnBuiltin] = &[
+    FnBuiltin::new(
+        "break",
+        "break [n]",
+        "Leaves the innermost loop, or the n-th enclosing one.",
+        loop_control,
+    ),
+    FnBuiltin::new(
+        "continue",
+        "continue [n]",
+        "Starts the next iteration of the innermost loop, or of the n-th enclosing one.",
+        loop_control,
+    ),
+    FnBuiltin::new(
+        "return",
+        "return [n]",
+        "Leaves a function or sourced script with status n, or with the status of the last command.",
+        return_,
+    ),
+    FnBuiltin::new(
+        "exit",
+        "exit [n]",
+        "Exits the shell with status n, or with the status of the last command.\n\
+         The first exit while there are jobs only warns about them.",
+        exit,
+    ),
+    FnBuiltin::new(
+        "logout",
+        "logout [n]",
+        "Exits a login shell, like exit.",
+        logout,
+    ),
+];
+
+fn loop_control(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    if executor.loop_depth == 0 {
+        return Err(NeocashError::Usage(
+            "only meaningful in a `for', `while', or `until' loop".to_string(),
+        ));
+    }
+    let levels = match args.get(1) {
+        Some(n) => n
+            .parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| NeocashError::invalid(n, "loop count out of range"))?,
+        None => 1,
+    };
+    let levels = levels.min(executor.loop_depth);
+    executor.loop_control = Some(if args[0] == "break" {
+        LoopControl::Break(levels)
+    } else {
+        LoopControl::Continue(levels)
+    });
+    Ok(0)
+}
+
+fn return_(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    if executor.function_depth == 0 && executor.source_depth == 0 {
+        return Err(NeocashError::Usage(
+            "can only `return' from a function or sourced script".to_string(),
+        ));
+    }
+    let status = match args.get(1) {
+        Some(n) => n.parse::<i32>().map_err(|_| numeric_argument_required(n))? & 0xff,
+        None => executor.state.last_status,
+    };
+    executor.return_status = Some(status);
+    Ok(status)
+}
+
+/// A misuse of `exit` or `return`, which makes them fail with 2 like in
+/// other shells
+fn numeric_argument_required(arg: &str) -> NeocashError {
+    NeocashError::Usage(format!("{}: numeric argument required", arg))
+}
+
+/// The shell exits once the commands in progress have been abandoned
+fn exit(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    if args.len() > 2 {
+        return Err(NeocashError::Usage("too many arguments".to_string()));
+    }
+    let status = match args.get(1) {
+        Some(n) => match n.parse::<i64>() {
+            Ok(n) => (n & 0xff) as i32,
+            Err(_) => {
+                executor.exit_status = Some(2);
+                return Err(numeric_argument_required(n));
+            }
+        },
+        None => executor.state.last_status,
+    };
+    if !executor.confirm_exit(io) {
+        return Ok(1);
+    }
+    executor.exit_status = Some(status);
+    Ok(status)
+}
+
+fn logout(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    if !executor.login {
+        return Err(NeocashError::Usage(
+            "not login shell: use `exit'".to_string(),
+        ));
+    }
+    exit(executor, args, io)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ShellConfig;
+    use crate::{EvalResult, Shell};
+
+    fn run(source: &str) -> EvalResult {
+        Shell::new(ShellConfig::default()).eval(source).unwrap()
+    }
+
+    #[test]
+    fn return_leaves_the_function_with_a_status() {
+        let result = run("f() { echo a; return 3; echo b; }\nf; echo $?");
+        assert_eq!(result.stdout, "a\n3\n");
+        let result = run("f() { false; return; }\nf; echo $?");
+        assert_eq!(result.stdout, "1\n");
+        let result = run("f() { for i in 1 2; do return $i; done; }\nf; echo $?");
+        assert_eq!(result.stdout, "1\n");
+    }
+
+    #[test]
+    fn return_fails_outside_of_functions() {
+        let result = run("return 1; echo $?");
+        assert_eq!(result.stdout, "2\n");
+        assert!(result.stderr.contains("can only `return' from a function"));
+
+        let result = run("f() { return abc; }\nf; echo $?");
+        assert_eq!(result.stdout, "2\n");
+        assert!(result.stderr.contains("abc: numeric argument required"));
+    }
+
+    #[test]
+    fn functions_get_their_own_positional_parameters() {
+        let result = run("f() { echo $# $1 $2; }\nset -- x\nf a b; echo $# $1");
+        assert_eq!(result.stdout, "2 a b\n1 x\n");
+    }
+
+    #[test]
+    fn local_variables_last_until_the_function_returns() {
+        let source =
+            "x=1\ng() { echo $x; }\nf() { local x=2 y; echo $x; g; y=3; }\nf; echo $x ${y-unset}";
+        assert_eq!(run(source).stdout, "2\n2\n1 unset\n");
+
+        let result = run("local x=1; echo $?");
+        assert_eq!(result.stdout, "2\n");
+        assert!(result.stderr.contains("can only be used in a function"));
+    }
+
+    #[test]
+    fn break_does_not_leave_the_function() {
+        let result = run("f() { break; echo in; }\nfor i in 1 2; do f; done; echo $i");
+        assert_eq!(result.stdout, "in\nin\n2\n");
+    }
+}
@@ -0,0 +1,155 @@
+// commands/jobs.rs
+
+use crate::commands::FnBuiltin;
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io, ProcessState};
+use nix::unistd::Pid;
+use std::io::Write;
+
+pub(super) const BUILTINS: &[FnBuiltin] = &[
+    FnBuiltin::new(
+        "jobs",
+        "jobs [-l | -p] [jobspec...]",
+        "Lists the jobs of the shell. -l adds process group ids, -p prints only them.",
+        jobs,
+    ),
+    FnBuiltin::new(
+        "fg",
+        "fg [jobspec]",
+        "Continues a job in the foreground and waits for it.",
+        fg,
+    ),
+    FnBuiltin::new(
+        "bg",
+        "bg [jobspec...]",
+        "Continues stopped jobs in the background.",
+        bg,
+    ),
+    FnBuiltin::new(
+        "wait",
+        "wait [jobspec | pid...]",
+        "Waits for the given jobs, or for all of them, to finish.",
+        wait,
+    ),
+    FnBuiltin::new(
+        "disown",
+        "disown [-a] [jobspec...]",
+        "Removes jobs from the job table without stopping them.",
+        disown,
+    ),
+];
+
+fn jobs(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let (flag, specs) = match args.get(1).map(String::as_str) {
+        Some(flag @ ("-l" | "-p")) => (Some(flag), &args[2..]),
+        _ => (None, &args[1..]),
+    };
+
+    executor.state.jobs.reap();
+    let ids = if specs.is_empty() {
+        executor.state.jobs.ids()
+    } else {
+        specs
+            .iter()
+            .map(|spec| executor.state.jobs.resolve(Some(spec)))
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut out = io.stdout();
+    for id in ids {
+        let marker = executor.state.jobs.marker(id);
+        let Some(job) = executor.state.jobs.get_mut(id) else {
+            continue;
+        };
+        let line = match flag {
+            Some("-p") => job.pgid.to_string(),
+            Some("-l") => job
+                .describe(marker)
+                .replacen("  ", &format!(" {} ", job.pgid), 1),
+            _ => job.describe(marker),
+        };
+        writeln!(out, "{}", line)?;
+
+        // Finished jobs are reported once and then forgotten
+        job.notified = true;
+        if let ProcessState::Done(_) = job.state() {
+            executor.state.jobs.remove(id);
+        }
+    }
+    Ok(0)
+}
+
+fn fg(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    if !executor.job_control() {
+        return Err("no job control".into());
+    }
+    let id = executor
+        .state
+        .jobs
+        .resolve(args.get(1).map(String::as_str))?;
+    executor.foreground_job(id, io)
+}
+
+fn bg(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    if !executor.job_control() {
+        return Err("no job control".into());
+    }
+    for spec in job_specs(args) {
+        let id = executor.state.jobs.resolve(spec)?;
+        executor.background_job(id, io)?;
+    }
+    Ok(0)
+}
+
+fn wait(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    if args.len() == 1 {
+        for id in executor.state.jobs.ids() {
+            executor.wait_job(id);
+        }
+        return Ok(0);
+    }
+
+    let mut status = 0;
+    for arg in &args[1..] {
+        let id = if arg.starts_with('%') {
+            Some(executor.state.jobs.resolve(Some(arg))?)
+        } else {
+            let pid = arg
+                .parse::<i32>()
+                .map_err(|_| NeocashError::invalid(arg, "not a pid or valid job spec"))?;
+            executor
+                .state
+                .jobs
+                .iter()
+                .find(|job| job.processes.iter().any(|p| p.pid == Pid::from_raw(pid)))
+                .map(|job| job.id)
+        };
+        status = match id {
+            Some(id) => executor.wait_job(id),
+            None => 127,
+        };
+    }
+    Ok(status)
+}
+
+fn disown(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    if args.get(1).map(String::as_str) == Some("-a") {
+        for id in executor.state.jobs.ids() {
+            executor.state.jobs.remove(id);
+        }
+        return Ok(0);
+    }
+    for spec in job_specs(args) {
+        let id = executor.state.jobs.resolve(spec)?;
+        executor.state.jobs.remove(id);
+    }
+    Ok(0)
+}
+
+/// Job specs given to `bg` or `disown`; none means the current job
+fn job_specs(args: &[String]) -> Vec<Option<&str>> {
+    match args.len() {
+        1 => vec![None],
+        _ => args[1..].iter().map(|s| Some(s.as_str())).collect(),
+    }
+}
@@ -0,0 +1,10 @@
+// commands/mod.rs
+
+mod alias;
+mod builtin;
+mod control;
+mod jobs;
+mod shell;
+mod vars;
+
+pub use builtin::{Builtin, Builtins, FnBuiltin};
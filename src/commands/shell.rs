@@ -0,0 +1,232 @@
+// commands/shell.rs
+
+use crate::commands::FnBuiltin;
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io, TrapCondition, find_in_path};
+use crate::syntax::is_reserved;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub(super) const BUILTINS: &[FnBuiltin] = &[
+    FnBuiltin::new(":", ":", "Does nothing and succeeds.", |_, _, _| Ok(0)),
+    FnBuiltin::new("true", "true", "Does nothing and succeeds.", |_, _, _| {
+        Ok(0)
+    }),
+    FnBuiltin::new("false", "false", "Does nothing and fails.", |_, _, _| Ok(1)),
+    FnBuiltin::new(
+        "cd",
+        "cd [dir | -]",
+        "Changes the working directory to dir, to $HOME without one, or to $OLDPWD with -.",
+        cd,
+    ),
+    FnBuiltin::new(
+        "edit",
+        "edit file",
+        "Opens a file in the editor set in the configuration.",
+        edit,
+    ),
+    FnBuiltin::new(
+        "source",
+        "source file [arg...]",
+        "Runs the commands of a file in the current shell. Arguments replace the\n\
+         positional parameters while it runs.",
+        source,
+    ),
+    FnBuiltin::new(
+        ".",
+        ". file [arg...]",
+        "Runs a file in the current shell, like source.",
+        source,
+    ),
+    FnBuiltin::new(
+        "trap",
+        "trap [-l | -p [condition...]] | [action] condition...",
+        "Runs action when a signal arrives, when a command fails (ERR) or when the\n\
+         shell exits (EXIT). An action of - resets the conditions and '' ignores\n\
+         the signals. Without an action the traps are listed.",
+        trap,
+    )
+    .with_completion(complete_trap),
+    FnBuiltin::new(
+        "help",
+        "help [builtin...]",
+        "Describes builtins, or lists them all.",
+        help,
+    ),
+    FnBuiltin::new(
+        "type",
+        "type [-t] name...",
+        "Tells how a name would be run: as an alias, keyword, function, builtin or\n\
+         file. -t prints only the kind.",
+        type_,
+    ),
+];
+
+fn cd(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let (path, print) = match args.get(1).map(String::as_str) {
+        Some("-") => (required_var(executor, "OLDPWD")?, true),
+        Some(path) => (path.to_string(), false),
+        None => (required_var(executor, "HOME")?, false),
+    };
+    executor.state.chdir(&path)?;
+    if print && let Some(pwd) = executor.state.vars.get("PWD") {
+        writeln!(io.stdout(), "{}", pwd)?;
+    }
+    Ok(0)
+}
+
+/// The value of a variable the builtin cannot do without
+fn required_var(executor: &Executor, name: &'static str) -> Result<String, NeocashError> {
+    executor.get_var(name).ok_or(NeocashError::NotSet(name))
+}
+
+fn edit(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let file = args
+        .get(1)
+        .ok_or_else(|| NeocashError::Usage("No file specified".to_string()))?;
+    let args = [executor.config.prompt.default_editor.clone(), file.clone()];
+    executor.run_program(&args, &[], io, false)
+}
+
+fn source(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let path = args
+        .get(1)
+        .ok_or_else(|| NeocashError::Usage("filename argument required".to_string()))?;
+    executor.source_file(path, &args[2..], io)
+}
+
+fn trap(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let mut out = io.stdout();
+    let args = match args.get(1).map(String::as_str) {
+        Some("--") => &args[2..],
+        Some("-l") => {
+            for condition in TrapCondition::all() {
+                if let TrapCondition::Signal(sig) = condition {
+                    writeln!(out, "{:2}) {}", sig as i32, sig)?;
+                }
+            }
+            return Ok(0);
+        }
+        _ => &args[1..],
+    };
+
+    let listing = match args.first().map(String::as_str) {
+        None => Some(&args[..0]),
+        Some("-p") => Some(&args[1..]),
+        _ => None,
+    };
+    if let Some(names) = listing {
+        let conditions = names
+            .iter()
+            .map(|name| TrapCondition::parse(name).ok_or_else(|| invalid_signal(name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (condition, action) in executor.traps() {
+            if conditions.is_empty() || conditions.contains(&condition) {
+                let action = action.replace('\'', r"'\''");
+                let name = condition.display_name();
+                writeln!(out, "trap -- '{}' {}", action, name)?;
+            }
+        }
+        return Ok(0);
+    }
+
+    // A lone condition, or conditions starting with a number, are reset
+    let (action, names) = match args {
+        [action, names @ ..] if action == "-" => (None, names),
+        [action, names @ ..] if !names.is_empty() && action.parse::<u32>().is_err() => {
+            (Some(action.as_str()), names)
+        }
+        _ => (None, args),
+    };
+    let mut status = 0;
+    for name in names {
+        match TrapCondition::parse(name) {
+            Some(condition) => executor.set_trap(condition, action),
+            None => {
+                executor.report(io, &invalid_signal(name), Some("trap"));
+                status = 1;
+            }
+        }
+    }
+    Ok(status)
+}
+
+fn invalid_signal(name: &str) -> NeocashError {
+    NeocashError::invalid(name, "invalid signal specification")
+}
+
+/// Condition names for every word after the action
+fn complete_trap(args: &[String]) -> Vec<String> {
+    if args.len() < 2 {
+        return Vec::new();
+    }
+    TrapCondition::all()
+        .map(|condition| condition.name().to_string())
+        .collect()
+}
+
+fn help(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let mut out = io.stdout();
+    if args.len() == 1 {
+        for builtin in executor.builtins.iter() {
+            writeln!(out, "{}", builtin.usage())?;
+        }
+        return Ok(0);
+    }
+
+    for name in &args[1..] {
+        let builtin = executor
+            .builtins
+            .get(name)
+            .ok_or_else(|| format!("no help topics match `{}'", name))?;
+        writeln!(out, "{}: {}", builtin.name(), builtin.usage())?;
+        for line in builtin.help().lines() {
+            writeln!(out, "    {}", line)?;
+        }
+    }
+    Ok(0)
+}
+
+fn type_(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let (terse, names) = match args.get(1).map(String::as_str) {
+        Some("-t") => (true, &args[2..]),
+        _ => (false, &args[1..]),
+    };
+    let mut out = io.stdout();
+    let mut status = 0;
+
+    let path = executor.get_var("PATH").unwrap_or_default();
+    for name in names {
+        let file = match name.contains('/') {
+            true => executor
+                .state
+                .resolve(name)
+                .is_file()
+                .then(|| PathBuf::from(name)),
+            false => find_in_path(name, &path),
+        };
+        let (kind, description) = if let Some(value) = executor.state.aliases.get(name) {
+            ("alias", format!("{} is aliased to `{}'", name, value))
+        } else if is_reserved(name) {
+            ("keyword", format!("{} is a shell keyword", name))
+        } else if let Some(body) = executor.state.functions.get(name) {
+            (
+                "function",
+                format!("{} is a function\n{}() {}", name, name, body),
+            )
+        } else if executor.builtins.contains(name) {
+            ("builtin", format!("{} is a shell builtin", name))
+        } else if let Some(file) = file {
+            ("file", format!("{} is {}", name, file.display()))
+        } else {
+            if !terse {
+                executor.report(io, &format!("{}: not found", name).into(), Some("type"));
+            }
+            status = 1;
+            continue;
+        };
+        let line = if terse { kind.to_string() } else { description };
+        writeln!(out, "{}", line)?;
+    }
+    Ok(status)
+}
@@ -0,0 +1,204 @@
+// commands/vars.rs
+
+use crate::commands::FnBuiltin;
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io, ShellOptions};
+use crate::syntax::is_name;
+use std::io::Write;
+
+pub(super) const BUILTINS: &[FnBuiltin] = &[
+    FnBuiltin::new(
+        "export",
+        "export [-n | -p] [name[=value]...]",
+        "Marks variables for the environment of commands, or lists them.\n\
+         -n removes the mark instead.",
+        export,
+    ),
+    FnBuiltin::new(
+        "unset",
+        "unset [-v | -f] name...",
+        "Removes variables (-v) or functions (-f). Without a flag a function is\n\
+         removed when there is no variable by that name.",
+        unset,
+    ),
+    FnBuiltin::new(
+        "local",
+        "local name[=value]...",
+        "Makes variables local to the function being run.",
+        local,
+    ),
+    FnBuiltin::new(
+        "shift",
+        "shift [n]",
+        "Drops the first n positional parameters, one by default.",
+        shift,
+    ),
+    FnBuiltin::new(
+        "set",
+        "set [-eufxC] [+eufxC] [-o option] [+o option] [--] [arg...]",
+        "Turns shell options on (-) or off (+) and sets the positional parameters.\n\
+         Without arguments the variables are listed; set -o and set +o list the options.",
+        set,
+    )
+    .with_completion(complete_set),
+];
+
+fn export(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let (flag, names) = match args.get(1).map(String::as_str) {
+        Some(flag @ ("-n" | "-p")) => (Some(flag), &args[2..]),
+        _ => (None, &args[1..]),
+    };
+
+    if names.is_empty() {
+        let mut out = io.stdout();
+        for (name, var) in executor.state.vars.iter() {
+            if !var.exported {
+                continue;
+            }
+            let line = match &var.value {
+                Some(value) => format!("export {}='{}'", name, value.replace('\'', r"'\''")),
+                None => format!("export {}", name),
+            };
+            writeln!(out, "{}", line)?;
+        }
+        return Ok(0);
+    }
+
+    for arg in names {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        if !is_name(name) {
+            return Err(NeocashError::InvalidIdentifier(arg.clone()));
+        }
+        if let Some(value) = value {
+            executor.state.vars.set(name, value);
+        }
+        match flag {
+            Some("-n") => executor.state.vars.unexport(name),
+            _ => executor.state.vars.export(name),
+        }
+    }
+    Ok(0)
+}
+
+fn unset(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    let (flag, names) = match args.get(1).map(String::as_str) {
+        Some(flag @ ("-v" | "-f")) => (Some(flag), &args[2..]),
+        _ => (None, &args[1..]),
+    };
+    for name in names {
+        if flag == Some("-f") {
+            executor.state.functions.remove(name);
+            continue;
+        }
+        if !is_name(name) {
+            return Err(NeocashError::InvalidIdentifier(name.clone()));
+        }
+        // Without a flag a function is unset if there is no variable
+        if executor.state.vars.unset(name).is_none() && flag.is_none() {
+            executor.state.functions.remove(name);
+        }
+    }
+    Ok(0)
+}
+
+fn local(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    for arg in &args[1..] {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        if !is_name(name) {
+            return Err(NeocashError::InvalidIdentifier(arg.clone()));
+        }
+        if !executor.state.vars.make_local(name) {
+            return Err(NeocashError::Usage(
+                "can only be used in a function".to_string(),
+            ));
+        }
+        if let Some(value) = value {
+            executor.state.vars.set(name, value);
+        }
+    }
+    Ok(0)
+}
+
+fn shift(executor: &mut Executor, args: &[String], _io: &Io) -> Result<i32, NeocashError> {
+    let count = match args.get(1) {
+        Some(n) => n
+            .parse::<usize>()
+            .map_err(|_| NeocashError::invalid(n, "numeric argument required"))?,
+        None => 1,
+    };
+    if count > executor.positional.len() {
+        return Ok(1);
+    }
+    executor.positional.drain(..count);
+    Ok(0)
+}
+
+fn set(executor: &mut Executor, args: &[String], io: &Io) -> Result<i32, NeocashError> {
+    let mut out = io.stdout();
+    if args.len() == 1 {
+        for (name, var) in executor.state.vars.iter() {
+            if let Some(value) = &var.value {
+                let value = value.replace('\'', r"'\''");
+                writeln!(out, "{}='{}'", name, value)?;
+            }
+        }
+        return Ok(0);
+    }
+
+    let mut i = 1;
+    while let Some(arg) = args.get(i) {
+        if arg == "--" {
+            executor.positional = args[i + 1..].to_vec();
+            return Ok(0);
+        }
+        let on = match arg.chars().next() {
+            Some('-') if arg.len() > 1 => true,
+            Some('+') if arg.len() > 1 => false,
+            _ => break,
+        };
+        i += 1;
+
+        if arg[1..] == *"o" {
+            let Some(name) = args.get(i) else {
+                for (name, value) in executor.state.options.iter() {
+                    let line = match (on, value) {
+                        (true, true) => format!("{:<15} on", name),
+                        (true, false) => format!("{:<15} off", name),
+                        (false, true) => format!("set -o {}", name),
+                        (false, false) => format!("set +o {}", name),
+                    };
+                    writeln!(out, "{}", line)?;
+                }
+                return Ok(0);
+            };
+            executor.state.options.set(name, on)?;
+            i += 1;
+            continue;
+        }
+        for flag in arg[1..].chars() {
+            executor.state.options.set_flag(flag, on)?;
+        }
+    }
+
+    if i < args.len() {
+        executor.positional = args[i..].to_vec();
+    }
+    Ok(0)
+}
+
+/// Option names after `-o` and `+o`
+fn complete_set(args: &[String]) -> Vec<String> {
+    match args.last().map(String::as_str) {
+        Some("-o" | "+o") => ShellOptions::default()
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,158 @@
+// completion/abbreviation.rs
+
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, RepeatCount};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Abbreviations shared between the shell and the line editor. A space
+/// typed after an abbreviation asks the completer to replace it, because
+/// a completion is the only edit that leaves the cursor after new text.
+#[derive(Clone, Default)]
+pub struct Abbreviations {
+    table: Arc<Mutex<HashMap<String, String>>>,
+    /// Set by [`AbbreviationHandler`] for the completion it triggers
+    expanding: Arc<AtomicBool>,
+}
+
+impl Abbreviations {
+    /// Replaces the abbreviations with the shell's current ones
+    pub fn update(&self, abbreviations: &HashMap<String, String>) {
+        if let Ok(mut table) = self.table.lock() {
+            table.clone_from(abbreviations);
+        }
+    }
+
+    /// Finds an abbreviation in command position that ends at byte `pos`.
+    /// Returns where the word starts and what it expands to.
+    pub fn find(&self, line: &str, pos: usize) -> Option<(usize, String)> {
+        let (before, after) = line.split_at(pos);
+        if after.starts_with(|c: char| !c.is_whitespace()) {
+            return None;
+        }
+
+        let start = before
+            .rfind(|c: char| c.is_whitespace() || is_separator(c))
+            .map_or(0, |i| i + 1);
+        let prefix = before[..start].trim_end();
+        if !prefix.is_empty() && !prefix.ends_with(is_separator) {
+            return None;
+        }
+
+        let table = self.table.lock().ok()?;
+        let expansion = table.get(&before[start..])?;
+        Some((start, expansion.clone()))
+    }
+
+    /// Returns the expansion requested by the space key, if any
+    pub fn take_expansion(&self, line: &str, pos: usize) -> Option<(usize, String)> {
+        if !self.expanding.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+        self.find(line, pos)
+    }
+
+    /// Expands an abbreviation at the end of an accepted line, which was
+    /// not followed by a space
+    pub fn expand_line(&self, line: String) -> String {
+        match self.find(&line, line.len()) {
+            Some((start, expansion)) => format!("{}{}", &line[..start], expansion),
+            None => line,
+        }
+    }
+}
+
+/// Expands the abbreviation before the cursor when space is pressed, so
+/// that the buffer and the history show the full command
+pub struct AbbreviationHandler {
+    abbreviations: Abbreviations,
+}
+
+impl AbbreviationHandler {
+    pub fn new(abbreviations: Abbreviations) -> Self {
+        Self { abbreviations }
+    }
+}
+
+impl ConditionalEventHandler for AbbreviationHandler {
+    fn handle(
+        &self,
+        _evt: &Event,
+        _n: RepeatCount,
+        _positive: bool,
+        ctx: &EventContext,
+    ) -> Option<Cmd> {
+        self.abbreviations.find(ctx.line(), ctx.pos())?;
+        self.abbreviations.expanding.store(true, Ordering::SeqCst);
+        Some(Cmd::Complete)
+    }
+}
+
+/// Characters after which a new command starts
+fn is_separator(c: char) -> bool {
+    matches!(c, ';' | '|' | '&' | '(')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abbreviations() -> Abbreviations {
+        let abbreviations = Abbreviations::default();
+        abbreviations.update(&HashMap::from([
+            ("gco".to_string(), "git checkout".to_string()),
+            ("l".to_string(), "ls -l".to_string()),
+        ]));
+        abbreviations
+    }
+
+    #[test]
+    fn finds_abbreviations_in_command_position() {
+        let abbreviations = abbreviations();
+        assert_eq!(
+            abbreviations.find("gco", 3),
+            Some((0, "git checkout".into()))
+        );
+        assert_eq!(
+            abbreviations.find("  gco ", 5),
+            Some((2, "git checkout".into()))
+        );
+        assert_eq!(abbreviations.find("a; l", 4), Some((3, "ls -l".into())));
+        assert_eq!(abbreviations.find("a &&l", 5), Some((4, "ls -l".into())));
+        assert_eq!(
+            abbreviations.find("(gco", 4),
+            Some((1, "git checkout".into()))
+        );
+    }
+
+    #[test]
+    fn leaves_other_words_alone() {
+        let abbreviations = abbreviations();
+        // An argument, a word the cursor is inside of, and a longer word
+        assert_eq!(abbreviations.find("echo gco", 8), None);
+        assert_eq!(abbreviations.find("gcox", 3), None);
+        assert_eq!(abbreviations.find("xgco", 4), None);
+        assert_eq!(abbreviations.find("", 0), None);
+    }
+
+    #[test]
+    fn expands_the_end_of_an_accepted_line() {
+        let abbreviations = abbreviations();
+        assert_eq!(abbreviations.expand_line("gco".into()), "git checkout");
+        assert_eq!(abbreviations.expand_line("cd /; l".into()), "cd /; ls -l");
+        assert_eq!(abbreviations.expand_line("echo l".into()), "echo l");
+        assert_eq!(abbreviations.expand_line("gco ".into()), "gco ");
+    }
+
+    #[test]
+    fn expands_on_space_only_when_asked() {
+        let abbreviations = abbreviations();
+        assert_eq!(abbreviations.take_expansion("gco", 3), None);
+        abbreviations.expanding.store(true, Ordering::SeqCst);
+        assert_eq!(
+            abbreviations.take_expansion("gco", 3),
+            Some((0, "git checkout".into()))
+        );
+        assert_eq!(abbreviations.take_expansion("gco", 3), None);
+    }
+}
@@ -1,6 +1,8 @@
 // completion/completer.rs
 
-use crate::completion::CompletionMenu;
+use crate::commands::Builtins;
+use crate::completion::{Abbreviations, CompletionMenu};
+use crate::exec::ShellState;
 use rustyline::{
     Context, Result as RLResult,
     completion::{Completer, Pair},
@@ -12,22 +14,65 @@ use std::path::PathBuf;
 
 #[derive(Clone)]
 pub struct ShellCompleter {
-    current_dir: PathBuf,
+    /// Working directory of the shell, which relative paths complete in
+    cwd: PathBuf,
+    /// `PATH` of the shell, searched for commands
+    path: String,
+    /// Names of aliases, shell functions and builtins, completed like
+    /// commands
+    shell_commands: Vec<String>,
+    /// Builtins, which complete their own arguments
+    builtins: Builtins,
+    abbreviations: Abbreviations,
+}
+
+impl Default for ShellCompleter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ShellCompleter {
+    /// Creates a completer for the working directory and `PATH` of this
+    /// process, until [`sync`](Self::sync) replaces them with a shell's
     pub fn new() -> Self {
         Self {
-            current_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            path: std::env::var("PATH").unwrap_or_default(),
+            shell_commands: Vec::new(),
+            builtins: Builtins::default(),
+            abbreviations: Abbreviations::default(),
         }
     }
 
+    /// The abbreviations this completer expands on behalf of
+    /// [`AbbreviationHandler`](crate::completion::AbbreviationHandler)
+    pub fn abbreviations(&self) -> Abbreviations {
+        self.abbreviations.clone()
+    }
+
+    /// Takes the working directory, `PATH`, aliases and functions of a
+    /// shell, and the builtins whose arguments it completes. Called before
+    /// every line is read, so that completion follows `cd` and the like.
+    pub fn sync(&mut self, state: &ShellState, builtins: &Builtins) {
+        self.cwd = state.cwd.clone();
+        self.path = state.vars.get("PATH").unwrap_or_default().to_string();
+
+        let functions = state.aliases.keys().chain(state.functions.keys()).cloned();
+        let mut names: Vec<String> = functions
+            .chain(builtins.names().map(String::from))
+            .collect();
+        names.sort();
+        names.dedup();
+        self.shell_commands = names;
+        self.builtins = builtins.clone();
+    }
+
     /// Returns a list of all commands available in the system's PATH.
     /// Returns a sorted vector of command names.
     /// Uses a HashSet to avoid duplicates.
     pub fn get_all_commands(&self) -> Vec<String> {
-        let path_var = std::env::var("PATH").unwrap_or_default();
-        let path_dirs = path_var.split(':').map(PathBuf::from);
+        let path_dirs = self.path.split(':').map(PathBuf::from);
 
         let mut commands = Vec::new();
         let mut seen = HashSet::new();
@@ -35,11 +80,11 @@ impl ShellCompleter {
         for dir in path_dirs {
             if let Ok(entries) = fs::read_dir(dir) {
                 for entry in entries.filter_map(|e| e.ok()) {
-                    if let Some(file_name) = entry.file_name().to_str() {
-                        if !seen.contains(file_name) {
-                            seen.insert(file_name.to_string());
-                            commands.push(file_name.to_string());
-                        }
+                    if let Some(file_name) = entry.file_name().to_str()
+                        && !seen.contains(file_name)
+                    {
+                        seen.insert(file_name.to_string());
+                        commands.push(file_name.to_string());
                     }
                 }
             }
@@ -49,7 +94,14 @@ impl ShellCompleter {
     }
 
     pub fn filter_commands(&self, prefix: &str) -> Vec<Pair> {
-        self.get_all_commands()
+        let mut commands = self.shell_commands.clone();
+        let mut seen: HashSet<String> = commands.iter().cloned().collect();
+        for command in self.get_all_commands() {
+            if seen.insert(command.clone()) {
+                commands.push(command);
+            }
+        }
+        commands
             .into_iter()
             .filter(|cmd| cmd.starts_with(prefix))
             .map(|cmd| Pair {
@@ -60,29 +112,29 @@ impl ShellCompleter {
     }
 
     fn complete_paths(&self, prefix: &str) -> Vec<Pair> {
-        let path = self.current_dir.join(prefix);
+        let path = self.cwd.join(prefix);
         let mut completions = Vec::new();
 
-        if let Some(parent) = path.parent() {
-            if let Ok(entries) = fs::read_dir(parent) {
-                let file_stem = path.file_stem().and_then(OsStr::to_str).unwrap_or("");
-
-                for entry in entries.filter_map(|e| e.ok()) {
-                    if let Some(name) = entry.file_name().to_str() {
-                        if name.starts_with(file_stem) {
-                            let full_path = parent.join(name);
-                            let display = if full_path.is_dir() {
-                                format!("{}/", name)
-                            } else {
-                                name.to_string()
-                            };
-
-                            completions.push(Pair {
-                                display: display.clone(),
-                                replacement: display,
-                            });
-                        }
-                    }
+        if let Some(parent) = path.parent()
+            && let Ok(entries) = fs::read_dir(parent)
+        {
+            let file_stem = path.file_stem().and_then(OsStr::to_str).unwrap_or("");
+
+            for entry in entries.filter_map(|e| e.ok()) {
+                if let Some(name) = entry.file_name().to_str()
+                    && name.starts_with(file_stem)
+                {
+                    let full_path = parent.join(name);
+                    let display = if full_path.is_dir() {
+                        format!("{}/", name)
+                    } else {
+                        name.to_string()
+                    };
+
+                    completions.push(Pair {
+                        display: display.clone(),
+                        replacement: display,
+                    });
                 }
             }
         }
@@ -91,6 +143,36 @@ impl ShellCompleter {
         completions
     }
 
+    /// Completes an argument of a builtin from the candidates it offers.
+    /// Returns where the word starts and the matching candidates.
+    fn complete_builtin_args(&self, line: &str, pos: usize) -> Option<(usize, Vec<Pair>)> {
+        let command = line[..pos].rsplit(['|', '&', ';']).next()?;
+        let mut words: Vec<String> = command.split_whitespace().map(String::from).collect();
+        let word = match command.ends_with(char::is_whitespace) {
+            true => String::new(),
+            false => words.pop()?,
+        };
+        let builtin = self.builtins.get(words.first()?)?;
+
+        let candidates: Vec<Pair> = builtin
+            .complete(&words)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(&word))
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+        (!candidates.is_empty()).then(|| (pos - word.len(), candidates))
+    }
+
+    /// Whether the cursor is in the command name, which is completed from
+    /// aliases, functions and `PATH` rather than from files
+    fn should_complete_command(&self, line: &str) -> bool {
+        let word = line.trim_start();
+        !word.is_empty() && !word.contains(char::is_whitespace) && !word.contains('/')
+    }
+
     fn should_complete_path(&self, line: &str) -> bool {
         let parts: Vec<&str> = line.split_whitespace().collect();
         if parts.is_empty() {
@@ -98,8 +180,7 @@ impl ShellCompleter {
         }
 
         let last_part = parts.last().unwrap();
-        !line.ends_with(char::is_whitespace)
-            && !last_part.contains(|c| matches!(c, '|' | '&' | ';'))
+        !line.ends_with(char::is_whitespace) && !last_part.contains(['|', '&', ';'])
     }
 
     fn show_completion_menu(&self, items: Vec<Pair>) -> Option<String> {
@@ -114,30 +195,46 @@ impl Completer for ShellCompleter {
     type Candidate = Pair;
 
     fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> RLResult<(usize, Vec<Pair>)> {
-        let prefix = if self.should_complete_path(line) {
-            line.split_whitespace().last().unwrap_or("")
-        } else {
-            line
-        };
+        if let Some((start, expansion)) = self.abbreviations.take_expansion(line, pos) {
+            let pair = Pair {
+                replacement: format!("{} ", expansion),
+                display: expansion,
+            };
+            return Ok((start, vec![pair]));
+        }
 
-        let completions = if self.should_complete_path(line) {
-            self.complete_paths(prefix)
-        } else {
-            self.filter_commands(prefix)
+        let (start, completions) = match self.complete_builtin_args(line, pos) {
+            Some(found) => found,
+            None => {
+                let prefix = if self.should_complete_path(line) {
+                    line.split_whitespace().last().unwrap_or("")
+                } else {
+                    line
+                };
+
+                let completions = if self.should_complete_command(line) {
+                    self.filter_commands(prefix)
+                } else if self.should_complete_path(line) {
+                    self.complete_paths(prefix)
+                } else {
+                    self.filter_commands(prefix)
+                };
+                (pos - prefix.len(), completions)
+            }
         };
 
-        if completions.len() > 1 {
-            if let Some(selected) = self.show_completion_menu(completions.clone()) {
-                return Ok((
-                    pos - prefix.len(),
-                    vec![Pair {
-                        display: selected.clone(),
-                        replacement: selected,
-                    }],
-                ));
-            }
+        if completions.len() > 1
+            && let Some(selected) = self.show_completion_menu(completions.clone())
+        {
+            return Ok((
+                start,
+                vec![Pair {
+                    display: selected.clone(),
+                    replacement: selected,
+                }],
+            ));
         }
 
-        Ok((pos - prefix.len(), completions))
+        Ok((start, completions))
     }
 }
@@ -95,15 +95,11 @@ impl CompletionMenu {
 
             if let Event::Key(KeyEvent { code, .. }) = event::read().ok()? {
                 match code {
-                    KeyCode::Up => {
-                        if self.selected > 0 {
-                            self.selected -= 1;
-                        }
+                    KeyCode::Up if self.selected > 0 => {
+                        self.selected -= 1;
                     }
-                    KeyCode::Down => {
-                        if self.selected < filtered_items.len().saturating_sub(1) {
-                            self.selected += 1;
-                        }
+                    KeyCode::Down if self.selected < filtered_items.len().saturating_sub(1) => {
+                        self.selected += 1;
                     }
                     KeyCode::Char(c) => {
                         self.filter.push(c);
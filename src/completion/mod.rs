@@ -1,7 +1,9 @@
 // completer/mod.rs
 
+mod abbreviation;
 mod completer;
 mod menu;
 
+pub use abbreviation::{AbbreviationHandler, Abbreviations};
 pub use completer::ShellCompleter;
 pub use menu::CompletionMenu;
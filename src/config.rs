@@ -1,5 +1,7 @@
 // config.rs
 
+use crate::error::NeocashError;
+use crate::exec::ShellOptions;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs, path::PathBuf};
 use dirs;
@@ -26,12 +28,44 @@ pub struct PromptConfig {
     pub default_editor: String, 
 }
 
+/// What a glob pattern that matches no files expands to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GlobNoMatch {
+    /// The pattern is passed on unchanged, like bash
+    #[default]
+    Pass,
+    /// The command fails, like zsh
+    Error,
+    /// The pattern is removed, like bash's `nullglob`
+    Null,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GlobConfig {
+    pub nomatch: GlobNoMatch,
+    /// Whether `*` and `?` match a leading `.` in file names
+    pub dotglob: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ShellConfig {
     pub prompt: PromptConfig,
     pub colors: HashMap<String, String>,
     pub history_size: usize,
     pub history_file: String,
+    #[serde(default)]
+    pub glob: GlobConfig,
+    /// Aliases defined at startup, like `ll = "ls -l"`
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Abbreviations expanded in the line editor, like `gco = "git checkout"`
+    #[serde(default)]
+    pub abbreviations: HashMap<String, String>,
+    /// Shell options turned on at startup, like `errexit = true`
+    #[serde(default)]
+    pub options: ShellOptions,
     #[serde(skip)]
     pub config_path: PathBuf,
 }
@@ -75,6 +109,10 @@ impl Default for ShellConfig {
             colors,
             history_size: 1000,
             history_file: "~/.local/share/ncash/history.txt".to_string(),
+            glob: GlobConfig::default(),
+            aliases: HashMap::new(),
+            abbreviations: HashMap::new(),
+            options: ShellOptions::default(),
             config_path: Self::get_default_config_path(),
         }
     }
@@ -98,8 +136,13 @@ impl ShellConfig {
                     }
                     config
                 }
-                Err(e) => {
-                    eprintln!("Config error: {}. Using defaults.", e);
+                Err(source) => {
+                    let error = NeocashError::Config {
+                        path: config_path.clone(),
+                        source,
+                    };
+                    error.report(None);
+                    eprintln!("Using the default configuration.");
 
                     let mut config = default_config;
                     config.config_path = config_path;
@@ -127,6 +170,28 @@ impl ShellConfig {
             .join("neocash/ncashrc")
     }
 
+    /// Script run at the start of an interactive shell
+    pub fn get_default_rc_path() -> PathBuf {
+        Self::startup_file("init.ncash")
+    }
+
+    /// Script run at the start of a login shell, before the rc script
+    pub fn get_profile_path() -> PathBuf {
+        Self::startup_file("profile.ncash")
+    }
+
+    /// Script run when a login shell exits
+    pub fn get_logout_path() -> PathBuf {
+        Self::startup_file("logout.ncash")
+    }
+
+    fn startup_file(name: &str) -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("neocash")
+            .join(name)
+    }
+
     pub fn get_active_config_path(&self) -> &PathBuf {
         &self.config_path
     }
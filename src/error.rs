@@ -0,0 +1,227 @@
+// error.rs
+
+use crate::syntax::ParseError;
+use nix::errno::Errno;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+/// Errors of the shell. The kind decides the exit status a failed command
+/// gets, so callers never have to look at the message.
+///
+/// `Display` gives the error's own message and `source()` its cause;
+/// the alternate form (`{:#}`) appends the causes, as reports do.
+#[derive(Debug)]
+pub enum NeocashError {
+    /// A command that is not a function, builtin or program in `PATH`
+    NotFound(String),
+    /// A program or script without execute permission
+    PermissionDenied(String),
+    /// A program or script that cannot be run for another reason, such as
+    /// a path that does not exist
+    CannotExecute { command: String, source: io::Error },
+    /// A syntax error in a command line or script
+    Parse(ParseError),
+    /// A configuration file that cannot be parsed
+    Config {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    /// A failed request to the package repository
+    Network {
+        context: &'static str,
+        source: reqwest::Error,
+    },
+    /// A failed file or system operation; the context tells what it was on
+    Io { context: String, source: io::Error },
+    /// The wrong use of a builtin, such as a missing argument or an
+    /// unknown option
+    Usage(String),
+    /// An argument a builtin cannot use, with the reason why
+    InvalidArgument {
+        argument: String,
+        reason: &'static str,
+    },
+    /// A word used where a variable name is needed
+    InvalidIdentifier(String),
+    /// A job specification that matches no job
+    NoSuchJob(String),
+    /// A variable a builtin needs, like `HOME` for `cd`
+    NotSet(&'static str),
+    /// Any other failure
+    Message(String),
+}
+
+impl NeocashError {
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// An argument a builtin cannot use, like `invalid("x", "numeric argument required")`
+    pub fn invalid(argument: impl Into<String>, reason: &'static str) -> Self {
+        Self::InvalidArgument {
+            argument: argument.into(),
+            reason,
+        }
+    }
+
+    /// Exit status of a command that failed with this error
+    pub fn status(&self) -> i32 {
+        match self {
+            Self::NotFound(_) => 127,
+            Self::PermissionDenied(_) => 126,
+            Self::CannotExecute { source, .. } if source.kind() == io::ErrorKind::NotFound => 127,
+            Self::CannotExecute { .. } => 126,
+            Self::Parse(_) | Self::Usage(_) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Writes `neocash: [command: ]message` to `out`, with the prefix in
+    /// red if `color` is set
+    pub fn write_to(&self, out: &mut dyn Write, color: bool, command: Option<&str>) {
+        let prefix = match color {
+            true => "\x1b[1;31mneocash:\x1b[0m",
+            false => "neocash:",
+        };
+        let result = match command {
+            Some(command) => writeln!(out, "{} {}: {:#}", prefix, command, self),
+            None => writeln!(out, "{} {:#}", prefix, self),
+        };
+        result.ok();
+    }
+
+    /// Reports the error on the shell's standard error
+    pub fn report(&self, command: Option<&str>) {
+        let no_color = std::env::var("NO_COLOR").ok();
+        let color = use_color(io::stderr().is_terminal(), no_color.as_deref());
+        self.write_to(&mut io::stderr(), color, command);
+    }
+}
+
+/// Whether messages for a terminal are colored; a non-empty `NO_COLOR`
+/// turns it off
+pub fn use_color(terminal: bool, no_color: Option<&str>) -> bool {
+    terminal && no_color.is_none_or(str::is_empty)
+}
+
+impl fmt::Display for NeocashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(command) => write!(f, "{}: command not found", command)?,
+            Self::PermissionDenied(command) => write!(f, "{}: Permission denied", command)?,
+            Self::CannotExecute { command, .. } => f.write_str(command)?,
+            Self::Parse(error) => write!(f, "{}", error)?,
+            Self::Config { path, .. } => write!(f, "{}", path.display())?,
+            Self::Network { context, .. } => f.write_str(context)?,
+            Self::Io { context, source } if context.is_empty() => f.write_str(&describe(source))?,
+            Self::Io { context, .. } => f.write_str(context)?,
+            Self::Usage(message) | Self::Message(message) => f.write_str(message)?,
+            Self::InvalidArgument { argument, reason } => write!(f, "{}: {}", argument, reason)?,
+            Self::InvalidIdentifier(name) => write!(f, "`{}': not a valid identifier", name)?,
+            Self::NoSuchJob(spec) => write!(f, "{}: no such job", spec)?,
+            Self::NotSet(name) => write!(f, "{} not set", name)?,
+        }
+
+        if f.alternate() {
+            let mut cause = std::error::Error::source(self);
+            while let Some(error) = cause {
+                match error.downcast_ref::<io::Error>() {
+                    Some(error) => write!(f, ": {}", describe(error))?,
+                    None => write!(f, ": {}", error)?,
+                }
+                cause = error.source();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The message of an I/O error without the `(os error N)` that std adds
+fn describe(error: &io::Error) -> String {
+    match error.raw_os_error() {
+        Some(code) => Errno::from_i32(code).desc().to_string(),
+        None => error.to_string(),
+    }
+}
+
+impl std::error::Error for NeocashError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // Without a context the error is the I/O error itself
+            Self::Io { context, source } if context.is_empty() => source.source(),
+            Self::CannotExecute { source, .. } | Self::Io { source, .. } => Some(source),
+            Self::Parse(error) => error.source(),
+            Self::Config { source, .. } => Some(source),
+            Self::Network { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for NeocashError {
+    fn from(message: String) -> Self {
+        Self::Message(message)
+    }
+}
+
+impl From<&str> for NeocashError {
+    fn from(message: &str) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+impl From<io::Error> for NeocashError {
+    fn from(source: io::Error) -> Self {
+        Self::io("", source)
+    }
+}
+
+impl From<ParseError> for NeocashError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<nix::Error> for NeocashError {
+    fn from(errno: nix::Error) -> Self {
+        Self::io("", io::Error::from(errno))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(error: &NeocashError, color: bool, command: Option<&str>) -> String {
+        let mut out = Vec::new();
+        error.write_to(&mut out, color, command);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn colors_only_terminals_without_no_color() {
+        assert!(use_color(true, None));
+        assert!(use_color(true, Some("")));
+        assert!(!use_color(true, Some("1")));
+        assert!(!use_color(false, None));
+    }
+
+    #[test]
+    fn writes_the_command_and_the_cause() {
+        let error = NeocashError::io("file", io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(error.to_string(), "file");
+        let text = written(&error, false, Some("cat"));
+        assert!(text.starts_with("neocash: cat: file: "), "{}", text);
+
+        let error = NeocashError::invalid("abc", "numeric argument required");
+        assert_eq!(error.status(), 1);
+        assert_eq!(
+            written(&error, true, None),
+            "\x1b[1;31mneocash:\x1b[0m abc: numeric argument required\n"
+        );
+    }
+}
@@ -0,0 +1,494 @@
+// exec/arith.rs
+
+use crate::exec::Variables;
+
+/// Deepest nesting of variables whose values are themselves expressions
+const MAX_DEPTH: usize = 64;
+
+/// Operators, longest first
+const OPERATORS: &[&str] = &[
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "(", ")", "<", ">", "!", "~", "&",
+    "^", "|", "?", ":", "=", ",",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Op(&'static str),
+}
+
+/// Evaluates an arithmetic expression as in `$((...))`. Variables are read
+/// and assigned in `vars`; unset or empty variables count as zero.
+pub fn evaluate(expr: &str, vars: &mut Variables) -> Result<i64, String> {
+    evaluate_nested(expr, vars, 0)
+}
+
+fn evaluate_nested(expr: &str, vars: &mut Variables, depth: usize) -> Result<i64, String> {
+    if depth > MAX_DEPTH {
+        return Err(format!(
+            "{}: expression recursion level exceeded",
+            expr.trim()
+        ));
+    }
+
+    let tokens = tokenize(expr).map_err(|e| format!("{}: {}", expr.trim(), e))?;
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+
+    let mut evaluator = Evaluator {
+        tokens,
+        pos: 0,
+        vars,
+        depth,
+        skip: 0,
+    };
+    let value = evaluator.comma();
+    let value = match value {
+        Ok(_) if evaluator.pos < evaluator.tokens.len() => {
+            let token = evaluator.tokens[evaluator.pos].to_string();
+            Err(format!(
+                "syntax error in expression (error token is \"{}\")",
+                token
+            ))
+        }
+        value => value,
+    };
+    value.map_err(|e| format!("{}: {}", expr.trim(), e))
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Op(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '_' | '#'))
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if c.is_ascii_digit() {
+                let n = parse_number(&word).ok_or_else(|| {
+                    format!("value too great for base (error token is \"{}\")", word)
+                })?;
+                tokens.push(Token::Number(n));
+            } else {
+                tokens.push(Token::Name(word));
+            }
+        } else {
+            let rest: String = chars[i..].iter().take(3).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| {
+                    format!(
+                        "syntax error: operand expected (error token is \"{}\")",
+                        rest
+                    )
+                })?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a decimal, octal (`017`), hexadecimal (`0x1f`) or `base#digits`
+/// integer constant
+fn parse_number(text: &str) -> Option<i64> {
+    let (base, digits) = if let Some((base, digits)) = text.split_once('#') {
+        (base.parse().ok().filter(|b| (2..=64).contains(b))?, digits)
+    } else if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        (16, hex)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (8, &text[1..])
+    } else {
+        (10, text)
+    };
+
+    if digits.is_empty() {
+        return None;
+    }
+    digits.chars().try_fold(0i64, |n, c| {
+        let digit = match c {
+            '0'..='9' => c as i64 - '0' as i64,
+            'a'..='z' => c as i64 - 'a' as i64 + 10,
+            'A'..='Z' if base <= 36 => c as i64 - 'A' as i64 + 10,
+            'A'..='Z' => c as i64 - 'A' as i64 + 36,
+            '@' => 62,
+            '_' => 63,
+            _ => return None,
+        };
+        (digit < base).then(|| n.wrapping_mul(base).wrapping_add(digit))
+    })
+}
+
+/// Recursive-descent evaluator with C operator precedence. While `skip` is
+/// non-zero, operands are parsed but assignments and errors are suppressed,
+/// for the unevaluated side of `&&`, `||` and `?:`.
+struct Evaluator<'v> {
+    tokens: Vec<Token>,
+    pos: usize,
+    vars: &'v mut Variables,
+    depth: usize,
+    skip: usize,
+}
+
+type Binary = fn(i64, i64) -> Option<i64>;
+
+/// Binary operators from lowest to highest precedence
+const LEVELS: &[&[(&str, Binary)]] = &[
+    &[("|", |a, b| Some(a | b))],
+    &[("^", |a, b| Some(a ^ b))],
+    &[("&", |a, b| Some(a & b))],
+    &[
+        ("==", |a, b| Some((a == b) as i64)),
+        ("!=", |a, b| Some((a != b) as i64)),
+    ],
+    &[
+        ("<", |a, b| Some((a < b) as i64)),
+        ("<=", |a, b| Some((a <= b) as i64)),
+        (">", |a, b| Some((a > b) as i64)),
+        (">=", |a, b| Some((a >= b) as i64)),
+    ],
+    &[
+        ("<<", |a, b| Some(a.wrapping_shl(b as u32))),
+        (">>", |a, b| Some(a.wrapping_shr(b as u32))),
+    ],
+    &[
+        ("+", |a, b| Some(a.wrapping_add(b))),
+        ("-", |a, b| Some(a.wrapping_sub(b))),
+    ],
+    &[
+        ("*", |a, b| Some(a.wrapping_mul(b))),
+        ("/", |a, b| (b != 0).then(|| a.wrapping_div(b))),
+        ("%", |a, b| (b != 0).then(|| a.wrapping_rem(b))),
+    ],
+];
+
+impl Evaluator<'_> {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        let found = self.peek_op() == Some(op);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.eat(op) {
+            return Ok(());
+        }
+        Err(match self.tokens.get(self.pos) {
+            Some(token) => format!("syntax error in expression (error token is \"{}\")", token),
+            None => format!("syntax error: `{}' expected", op),
+        })
+    }
+
+    fn comma(&mut self) -> Result<i64, String> {
+        let mut value = self.assignment()?;
+        while self.eat(",") {
+            value = self.assignment()?;
+        }
+        Ok(value)
+    }
+
+    fn assignment(&mut self) -> Result<i64, String> {
+        let assign_op = match (self.tokens.get(self.pos), self.tokens.get(self.pos + 1)) {
+            (Some(Token::Name(name)), Some(Token::Op(op)))
+                if op.ends_with('=') && !matches!(*op, "==" | "!=" | "<=" | ">=") =>
+            {
+                Some((name.clone(), *op))
+            }
+            _ => None,
+        };
+        let Some((name, op)) = assign_op else {
+            return self.conditional();
+        };
+        self.pos += 2;
+
+        let rhs = self.assignment()?;
+        let value = match op {
+            "=" => rhs,
+            op => {
+                let binary = &op[..op.len() - 1];
+                let current = self.variable(&name)?;
+                self.apply(binary, current, rhs)?
+            }
+        };
+        self.assign(&name, value);
+        Ok(value)
+    }
+
+    fn conditional(&mut self) -> Result<i64, String> {
+        let condition = self.logical_or()?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+
+        let then = self.skipping(condition == 0, Self::comma)?;
+        self.expect(":")?;
+        let otherwise = self.skipping(condition != 0, Self::conditional)?;
+        Ok(if condition != 0 { then } else { otherwise })
+    }
+
+    fn logical_or(&mut self) -> Result<i64, String> {
+        let mut value = self.logical_and()?;
+        while self.eat("||") {
+            let rhs = self.skipping(value != 0, Self::logical_and)?;
+            value = (value != 0 || rhs != 0) as i64;
+        }
+        Ok(value)
+    }
+
+    fn logical_and(&mut self) -> Result<i64, String> {
+        let mut value = self.binary(0)?;
+        while self.eat("&&") {
+            let rhs = self.skipping(value == 0, |e| e.binary(0))?;
+            value = (value != 0 && rhs != 0) as i64;
+        }
+        Ok(value)
+    }
+
+    /// Evaluates `f` without side effects if `skip` is set
+    fn skipping(
+        &mut self,
+        skip: bool,
+        f: impl FnOnce(&mut Self) -> Result<i64, String>,
+    ) -> Result<i64, String> {
+        self.skip += skip as usize;
+        let value = f(self);
+        self.skip -= skip as usize;
+        value
+    }
+
+    /// Left-associative binary operators of precedence `level` and above
+    fn binary(&mut self, level: usize) -> Result<i64, String> {
+        let Some(ops) = LEVELS.get(level) else {
+            return self.power();
+        };
+
+        let mut value = self.binary(level + 1)?;
+        while let Some(op) = self.peek_op().filter(|op| ops.iter().any(|(o, _)| o == op)) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            value = self.apply(op, value, rhs)?;
+        }
+        Ok(value)
+    }
+
+    fn apply(&self, op: &str, lhs: i64, rhs: i64) -> Result<i64, String> {
+        let (_, f) = LEVELS
+            .iter()
+            .flat_map(|ops| ops.iter())
+            .find(|(o, _)| *o == op)
+            .expect("known operator");
+        match f(lhs, rhs) {
+            Some(value) => Ok(value),
+            None if self.skip > 0 => Ok(0),
+            None => Err(format!("division by 0 (error token is \"{}\")", rhs)),
+        }
+    }
+
+    /// `**` is right-associative and binds tighter than `*`
+    fn power(&mut self) -> Result<i64, String> {
+        let base = self.unary()?;
+        if !self.eat("**") {
+            return Ok(base);
+        }
+        let exponent = self.power()?;
+        if exponent < 0 {
+            return match self.skip {
+                0 => Err("exponent less than 0".to_string()),
+                _ => Ok(0),
+            };
+        }
+        Ok(base.wrapping_pow(exponent.min(u32::MAX as i64) as u32))
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        if let Some(op @ ("++" | "--")) = self.peek_op() {
+            self.pos += 1;
+            let Some(Token::Name(name)) = self.tokens.get(self.pos).cloned() else {
+                return Err("syntax error: operand expected".to_string());
+            };
+            self.pos += 1;
+            let delta = if op == "++" { 1 } else { -1 };
+            let value = self.variable(&name)?.wrapping_add(delta);
+            self.assign(&name, value);
+            return Ok(value);
+        }
+
+        match self.peek_op() {
+            Some("+") => {
+                self.pos += 1;
+                self.unary()
+            }
+            Some("-") => {
+                self.pos += 1;
+                Ok(self.unary()?.wrapping_neg())
+            }
+            Some("!") => {
+                self.pos += 1;
+                Ok((self.unary()? == 0) as i64)
+            }
+            Some("~") => {
+                self.pos += 1;
+                Ok(!self.unary()?)
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<i64, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(n)) => Ok(n),
+            Some(Token::Name(name)) => {
+                let value = self.variable(&name)?;
+                if let Some(op @ ("++" | "--")) = self.peek_op() {
+                    self.pos += 1;
+                    let delta = if op == "++" { 1 } else { -1 };
+                    self.assign(&name, value.wrapping_add(delta));
+                }
+                Ok(value)
+            }
+            Some(Token::Op("(")) => {
+                let value = self.comma()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(token) => Err(format!(
+                "syntax error: operand expected (error token is \"{}\")",
+                token
+            )),
+            None => Err("syntax error: operand expected".to_string()),
+        }
+    }
+
+    /// The value of a variable. A value that is not a number is evaluated
+    /// as an expression itself.
+    fn variable(&mut self, name: &str) -> Result<i64, String> {
+        let value = self.vars.get(name).unwrap_or_default().trim().to_string();
+        if value.is_empty() {
+            return Ok(0);
+        }
+        if let Some(n) = parse_number(&value) {
+            return Ok(n);
+        }
+        if self.skip > 0 {
+            return Ok(0);
+        }
+        evaluate_nested(&value, self.vars, self.depth + 1)
+    }
+
+    fn assign(&mut self, name: &str, value: i64) {
+        if self.skip == 0 {
+            self.vars.set(name, value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expr: &str) -> Result<i64, String> {
+        evaluate(expr, &mut Variables::default())
+    }
+
+    #[test]
+    fn follows_c_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval("2 ** 3 ** 2"), Ok(512));
+        assert_eq!(eval("-2 ** 2"), Ok(4));
+        assert_eq!(eval("7 / 2 - 7 % 2"), Ok(2));
+        assert_eq!(eval("1 << 4 | 1"), Ok(17));
+        assert_eq!(eval("3 > 2 && 2 > 3 || !0"), Ok(1));
+        assert_eq!(eval("0 ? 1 : 2 ? 3 : 4"), Ok(3));
+        assert_eq!(eval("1, 2"), Ok(2));
+        assert_eq!(eval(""), Ok(0));
+    }
+
+    #[test]
+    fn reads_number_bases() {
+        assert_eq!(eval("010"), Ok(8));
+        assert_eq!(eval("0x1f"), Ok(31));
+        assert_eq!(eval("2#101"), Ok(5));
+        assert_eq!(eval("36#z"), Ok(35));
+        assert!(eval("09").is_err());
+    }
+
+    #[test]
+    fn reads_and_assigns_variables() {
+        let mut vars = Variables::default();
+        vars.set("x", "5");
+        vars.set("expr", "x * 2");
+        assert_eq!(evaluate("expr + y", &mut vars), Ok(10));
+        assert_eq!(evaluate("x += 2, x++, ++x", &mut vars), Ok(9));
+        assert_eq!(vars.get("x"), Some("9"));
+        assert_eq!(evaluate("y = x--", &mut vars), Ok(9));
+        assert_eq!(vars.get("y"), Some("9"));
+        assert_eq!(vars.get("x"), Some("8"));
+    }
+
+    #[test]
+    fn skips_assignments_in_branches_not_taken() {
+        let mut vars = Variables::default();
+        assert_eq!(
+            evaluate("0 && (a = 1), 1 || (b = 1), 1 ? c : (d = 1)", &mut vars),
+            Ok(0)
+        );
+        assert_eq!(evaluate("0 / 1 && 1 / 0", &mut vars), Ok(0));
+        for name in ["a", "b", "d"] {
+            assert_eq!(vars.get(name), None);
+        }
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(eval("1 / 0").unwrap_err().contains("division by 0"));
+        assert!(eval("1 % 0").unwrap_err().contains("division by 0"));
+        assert!(
+            eval("2 ** -1")
+                .unwrap_err()
+                .contains("exponent less than 0")
+        );
+        assert!(eval("1 +").unwrap_err().contains("operand expected"));
+        assert!(eval("(1").is_err());
+        assert!(eval("1 2").unwrap_err().contains("error token is \"2\""));
+
+        let mut vars = Variables::default();
+        vars.set("a", "a");
+        let error = evaluate("a", &mut vars).unwrap_err();
+        assert!(error.contains("recursion level exceeded"));
+    }
+}
@@ -0,0 +1,219 @@
+// exec/brace.rs
+
+use crate::syntax::{Word, WordPart};
+
+/// A word broken into unquoted characters, which may form braces, and
+/// other parts, which are never part of the brace syntax
+#[derive(Debug, Clone, Copy)]
+enum Item<'w> {
+    Char(char),
+    Part(&'w WordPart),
+}
+
+/// Expands `{a,b,c}` and `{1..10}` in a word into one word per alternative.
+/// Braces that are quoted, or that contain neither a comma nor a valid
+/// sequence, are left alone.
+pub fn brace_expand(word: &Word) -> Vec<Word> {
+    let has_brace = word
+        .parts
+        .iter()
+        .any(|part| matches!(part, WordPart::Literal(text) if text.contains('{')));
+    if !has_brace {
+        return vec![word.clone()];
+    }
+
+    let items: Vec<Item> = word
+        .parts
+        .iter()
+        .flat_map(|part| match part {
+            WordPart::Literal(text) => text.chars().map(Item::Char).collect(),
+            part => vec![Item::Part(part)],
+        })
+        .collect();
+    expand(&items).iter().map(|items| rebuild(items)).collect()
+}
+
+fn expand<'w>(items: &[Item<'w>]) -> Vec<Vec<Item<'w>>> {
+    for open in 0..items.len() {
+        if !matches!(items[open], Item::Char('{')) {
+            continue;
+        }
+        let Some((close, alternatives)) = parse_brace(items, open) else {
+            continue;
+        };
+
+        let mut words = Vec::new();
+        for alternative in alternatives {
+            let mut combined = items[..open].to_vec();
+            combined.extend(alternative);
+            combined.extend_from_slice(&items[close + 1..]);
+            words.extend(expand(&combined));
+        }
+        return words;
+    }
+    vec![items.to_vec()]
+}
+
+/// Parses the brace expression opening at `open`. Returns the index of the
+/// closing brace and the alternatives it expands to.
+fn parse_brace<'w>(items: &[Item<'w>], open: usize) -> Option<(usize, Vec<Vec<Item<'w>>>)> {
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut close = None;
+    for (i, item) in items.iter().enumerate().skip(open) {
+        match item {
+            Item::Char('{') => depth += 1,
+            Item::Char('}') => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+            }
+            Item::Char(',') if depth == 1 => commas.push(i),
+            _ => {}
+        }
+    }
+    let close = close?;
+
+    if !commas.is_empty() {
+        let bounds: Vec<usize> = [open].into_iter().chain(commas).chain([close]).collect();
+        let alternatives = bounds
+            .windows(2)
+            .map(|w| items[w[0] + 1..w[1]].to_vec())
+            .collect();
+        return Some((close, alternatives));
+    }
+
+    let text = items[open + 1..close]
+        .iter()
+        .map(|item| match item {
+            Item::Char(c) => Some(*c),
+            Item::Part(_) => None,
+        })
+        .collect::<Option<String>>()?;
+    let alternatives = sequence(&text)?
+        .into_iter()
+        .map(|s| s.chars().map(Item::Char).collect())
+        .collect();
+    Some((close, alternatives))
+}
+
+/// Expands `start..end` or `start..end..step`, where start and end are both
+/// integers or both single characters
+fn sequence(text: &str) -> Option<Vec<String>> {
+    let bounds: Vec<&str> = text.split("..").collect();
+    let (start, end, step) = match bounds.as_slice() {
+        [start, end] => (*start, *end, 1),
+        [start, end, step] => (
+            *start,
+            *end,
+            step.parse::<i64>().ok()?.unsigned_abs().max(1),
+        ),
+        _ => return None,
+    };
+
+    if let (Ok(first), Ok(last)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        // `{01..10}` pads every number to the same width
+        let padded = |s: &str| {
+            s.trim_start_matches('-').len() > 1 && s.trim_start_matches('-').starts_with('0')
+        };
+        let width = if padded(start) || padded(end) {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+        let values = range(first, last, step);
+        return Some(
+            values
+                .map(|n| format!("{:0width$}", n, width = width))
+                .collect(),
+        );
+    }
+
+    // Characters only pair with characters; `{1..b}` is no sequence
+    let character = |c: char| c.is_ascii() && !c.is_ascii_digit();
+    let (mut first, mut last) = (start.chars(), end.chars());
+    match (first.next(), first.next(), last.next(), last.next()) {
+        (Some(first), None, Some(last), None) if character(first) && character(last) => {
+            let values = range(first as i64, last as i64, step);
+            Some(values.map(|n| (n as u8 as char).to_string()).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Counts from `first` to `last` inclusive, up or down
+fn range(first: i64, last: i64, step: u64) -> Box<dyn Iterator<Item = i64>> {
+    if first <= last {
+        Box::new((first..=last).step_by(step as usize))
+    } else {
+        Box::new((last..=first).rev().step_by(step as usize))
+    }
+}
+
+fn rebuild(items: &[Item]) -> Word {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    for item in items {
+        match item {
+            Item::Char(c) => literal.push(*c),
+            Item::Part(part) => {
+                if !literal.is_empty() {
+                    parts.push(WordPart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push((*part).clone());
+            }
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(WordPart::Literal(literal));
+    }
+    Word { parts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::{Command, parse};
+
+    /// The words a word written in shell syntax expands to
+    fn expand(source: &str) -> Vec<String> {
+        let list = parse(source).unwrap();
+        let Command::Simple(command) = &list.items[0].and_or.first.commands[0] else {
+            panic!("not a simple command: {}", source);
+        };
+        brace_expand(&command.words[0])
+            .iter()
+            .map(Word::unquoted_text)
+            .collect()
+    }
+
+    #[test]
+    fn expands_alternatives() {
+        assert_eq!(expand("a{b,c}d"), ["abd", "acd"]);
+        assert_eq!(expand("{a,b}{1,2}"), ["a1", "a2", "b1", "b2"]);
+        assert_eq!(expand("{a,{b,c}x}"), ["a", "bx", "cx"]);
+        assert_eq!(expand("x{,y}"), ["x", "xy"]);
+    }
+
+    #[test]
+    fn expands_sequences() {
+        assert_eq!(expand("{1..3}"), ["1", "2", "3"]);
+        assert_eq!(expand("{3..-1..2}"), ["3", "1", "-1"]);
+        assert_eq!(expand("{08..10}"), ["08", "09", "10"]);
+        assert_eq!(expand("{c..a}"), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn leaves_other_braces_alone() {
+        for word in [
+            "{a}", "{}", "{a,b", "'{a,b}'", "\\{a,b}", "{1..b}", "{ab..c}",
+        ] {
+            let text = expand(word);
+            assert_eq!(text.len(), 1, "{}", word);
+        }
+        assert_eq!(expand("{a}"), ["{a}"]);
+        assert_eq!(expand("\"{a,b}\"x{1,2}"), ["{a,b}x1", "{a,b}x2"]);
+    }
+}
@@ -0,0 +1,163 @@
+// exec/compound.rs
+
+use crate::error::NeocashError;
+use crate::exec::process::fork_with;
+use crate::exec::{Executor, Io, Pattern, Process, ProcessState};
+use crate::syntax::{CaseArm, CompoundCommand, List, Word};
+
+/// A pending `break` or `continue`, with the number of enclosing loops it
+/// still has to leave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopControl {
+    Break(usize),
+    Continue(usize),
+}
+
+impl Executor {
+    pub(crate) fn execute_compound(&mut self, compound: &CompoundCommand, io: &Io) -> i32 {
+        match compound {
+            CompoundCommand::If {
+                branches,
+                else_branch,
+            } => {
+                for (condition, body) in branches {
+                    let status = self.execute_condition(condition, io);
+                    if self.aborted(io) {
+                        return status;
+                    }
+                    if status == 0 {
+                        return self.execute_body(body, io);
+                    }
+                }
+                match else_branch {
+                    Some(body) => self.execute_body(body, io),
+                    None => 0,
+                }
+            }
+            CompoundCommand::While {
+                condition,
+                body,
+                until,
+            } => self.execute_while(condition, body, *until, io),
+            CompoundCommand::For { name, words, body } => {
+                let values = match words {
+                    Some(words) => match self.expand_words(words, io) {
+                        Ok(values) => values,
+                        Err(e) => {
+                            self.report(io, &e, None);
+                            return 1;
+                        }
+                    },
+                    None => self.positional.clone(),
+                };
+                self.execute_for(name, &values, body, io)
+            }
+            CompoundCommand::Case { word, arms } => match self.execute_case(word, arms, io) {
+                Ok(status) => status,
+                Err(e) => {
+                    self.report(io, &e, None);
+                    1
+                }
+            },
+            CompoundCommand::Subshell(body) => self.execute_subshell(body, io),
+            CompoundCommand::Group(body) => self.execute_body(body, io),
+        }
+    }
+
+    /// Runs `body` in a forked copy of the shell, so that changes to
+    /// variables or the working directory do not outlive it
+    fn execute_subshell(&mut self, body: &List, io: &Io) -> i32 {
+        let result = fork_with(self.launch, || {
+            self.enter_subshell();
+            let status = self.execute_body(body, io);
+            self.finish(status, io)
+        });
+        match result {
+            Ok(pid) => {
+                self.joined(pid);
+                let process = Process {
+                    pid,
+                    state: ProcessState::Running,
+                };
+                self.wait_foreground(vec![process], None, io)[0]
+            }
+            Err(e) => {
+                self.report(io, &NeocashError::io("fork", e), None);
+                1
+            }
+        }
+    }
+
+    fn execute_while(&mut self, condition: &List, body: &List, until: bool, io: &Io) -> i32 {
+        let mut status = 0;
+        self.loop_depth += 1;
+        loop {
+            let result = self.execute_condition(condition, io);
+            if self.end_iteration(io) {
+                status = result;
+                break;
+            }
+            if (result == 0) == until {
+                break;
+            }
+            status = self.execute_body(body, io);
+            if self.end_iteration(io) {
+                break;
+            }
+        }
+        self.loop_depth -= 1;
+        status
+    }
+
+    fn execute_for(&mut self, name: &str, values: &[String], body: &List, io: &Io) -> i32 {
+        let mut status = 0;
+        self.loop_depth += 1;
+        for value in values {
+            self.state.vars.set(name, value.as_str());
+            status = self.execute_body(body, io);
+            if self.end_iteration(io) {
+                break;
+            }
+        }
+        self.loop_depth -= 1;
+        status
+    }
+
+    fn execute_case(
+        &mut self,
+        word: &Word,
+        arms: &[CaseArm],
+        io: &Io,
+    ) -> Result<i32, NeocashError> {
+        let word = self.expand_word(word, io)?;
+        for arm in arms {
+            for pattern in &arm.patterns {
+                let pattern = Pattern::new(&self.expand_pattern(pattern, io)?);
+                if pattern.matches(&word) {
+                    return Ok(self.execute_body(&arm.body, io));
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    /// Consumes a pending `break` or `continue` at the end of a loop
+    /// iteration. Returns `true` if the loop has to stop.
+    fn end_iteration(&mut self, io: &Io) -> bool {
+        if self.check_interrupt(io) || self.unwinding() {
+            return true;
+        }
+        match self.loop_control.take() {
+            None | Some(LoopControl::Continue(1)) => false,
+            Some(LoopControl::Break(1)) => true,
+            Some(LoopControl::Break(n)) => {
+                self.loop_control = Some(LoopControl::Break(n - 1));
+                true
+            }
+            Some(LoopControl::Continue(n)) => {
+                self.loop_control = Some(LoopControl::Continue(n - 1));
+                true
+            }
+        }
+    }
+}
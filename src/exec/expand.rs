@@ -0,0 +1,476 @@
+// exec/expand.rs
+
+use crate::config::GlobNoMatch;
+use crate::error::NeocashError;
+use crate::exec::arith;
+use crate::exec::brace::brace_expand;
+use crate::exec::glob::glob;
+use crate::exec::pattern::Pattern;
+use crate::exec::process::{exit_status, fork_with, wait_pid};
+use crate::exec::{Executor, Io};
+use crate::signals;
+use crate::syntax::{List, ParamExpansion, ParamOp, ReplaceMode, Word, WordPart, is_name};
+use nix::unistd::User;
+use std::io::Read;
+
+/// A field produced by expansion
+#[derive(Debug, Default)]
+struct Field {
+    /// The text after quote removal
+    text: String,
+    /// The same text with quoted characters escaped, for pattern matching
+    pattern: String,
+    /// Whether an unquoted `*`, `?` or `[` makes the field a glob pattern
+    glob: bool,
+}
+
+/// Accumulates the fields a word expands to
+struct Fields {
+    fields: Vec<Field>,
+    current: Field,
+    /// Whether the current field exists even if it is empty, as after `""`
+    started: bool,
+    /// Field separators, or `None` when splitting is disabled
+    ifs: Option<String>,
+}
+
+impl Fields {
+    fn new(ifs: Option<String>) -> Self {
+        Self {
+            fields: Vec::new(),
+            current: Field::default(),
+            started: false,
+            ifs,
+        }
+    }
+
+    /// Appends unquoted text that is not subject to field splitting
+    fn push_str(&mut self, text: &str) {
+        for c in text.chars() {
+            self.push_unquoted(c);
+        }
+    }
+
+    fn push_unquoted(&mut self, c: char) {
+        self.current.text.push(c);
+        self.current.pattern.push(c);
+        self.current.glob |= matches!(c, '*' | '?' | '[');
+        self.started = true;
+    }
+
+    /// Appends quoted text. The current field exists afterwards even if
+    /// `text` is empty, as for `""`.
+    fn push_quoted(&mut self, text: &str) {
+        self.current.text.push_str(text);
+        self.current.pattern.push_str(&Pattern::escape(text));
+        self.started = true;
+    }
+
+    /// Appends `"$@"`, which makes every value a field of its own. Without
+    /// any values a field that holds nothing else is dropped.
+    fn push_each(&mut self, values: &[String]) {
+        if values.is_empty() && self.current.text.is_empty() {
+            self.started = false;
+        }
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                self.end_field();
+            }
+            self.push_quoted(value);
+        }
+    }
+
+    /// Appends the result of an expansion, which is split on `IFS` unless
+    /// it is quoted
+    fn push_expansion(&mut self, value: &str, quoted: bool) {
+        if quoted {
+            self.push_quoted(value);
+        } else {
+            self.push_split(value);
+        }
+    }
+
+    /// Appends the result of an unquoted expansion, splitting it on `IFS`
+    fn push_split(&mut self, value: &str) {
+        let Some(ifs) = self.ifs.clone().filter(|ifs| !ifs.is_empty()) else {
+            self.push_str(value);
+            return;
+        };
+
+        for c in value.chars() {
+            if !ifs.contains(c) {
+                self.push_unquoted(c);
+            } else if !c.is_whitespace() || self.started {
+                // A non-blank separator always ends a field, even an empty one
+                self.end_field();
+            }
+        }
+    }
+
+    fn end_field(&mut self) {
+        self.fields.push(std::mem::take(&mut self.current));
+        self.started = false;
+    }
+
+    fn finish(mut self) -> Vec<Field> {
+        if self.started {
+            self.end_field();
+        }
+        self.fields
+    }
+
+    /// The fields joined into one string, for contexts without splitting
+    fn join(self) -> String {
+        let fields: Vec<String> = self.finish().into_iter().map(|f| f.text).collect();
+        fields.join(" ")
+    }
+
+    /// Like [`Fields::join`], but with quoted characters escaped
+    fn join_pattern(self) -> String {
+        let fields: Vec<String> = self.finish().into_iter().map(|f| f.pattern).collect();
+        fields.join(" ")
+    }
+}
+
+impl Executor {
+    /// Expands words into command arguments: brace, tilde, parameter,
+    /// command and arithmetic expansion, field splitting, pathname expansion
+    /// and quote removal
+    pub fn expand_words(&mut self, words: &[Word], io: &Io) -> Result<Vec<String>, NeocashError> {
+        let ifs = self.get_var("IFS").unwrap_or_else(|| " \t\n".to_string());
+        let mut args = Vec::new();
+        for word in words.iter().flat_map(brace_expand) {
+            let mut fields = Fields::new(Some(ifs.clone()));
+            self.expand_parts(&word.parts, &mut fields, false, io)?;
+            for field in fields.finish() {
+                // A lone `[` without its closing bracket is not a pattern
+                if !field.glob
+                    || self.state.options.noglob
+                    || Pattern::new(&field.pattern).is_literal()
+                {
+                    args.push(field.text);
+                    continue;
+                }
+
+                let matches = glob(&field.pattern, &self.state.cwd, self.config.glob.dotglob);
+                if !matches.is_empty() {
+                    args.extend(matches);
+                    continue;
+                }
+                match self.config.glob.nomatch {
+                    GlobNoMatch::Pass => args.push(field.text),
+                    GlobNoMatch::Null => {}
+                    GlobNoMatch::Error => {
+                        return Err(format!("no matches found: {}", field.text).into());
+                    }
+                }
+            }
+        }
+        Ok(args)
+    }
+
+    /// Expands a word into a single string without field splitting, as for
+    /// assignment values and redirection targets
+    pub fn expand_word(&mut self, word: &Word, io: &Io) -> Result<String, NeocashError> {
+        let mut fields = Fields::new(None);
+        self.expand_parts(&word.parts, &mut fields, false, io)?;
+        Ok(fields.join())
+    }
+
+    /// Expands a word into a pattern string for [`Pattern::new`]
+    pub(crate) fn expand_pattern(&mut self, word: &Word, io: &Io) -> Result<String, NeocashError> {
+        let mut fields = Fields::new(None);
+        self.expand_parts(&word.parts, &mut fields, false, io)?;
+        Ok(fields.join_pattern())
+    }
+
+    fn expand_parts(
+        &mut self,
+        parts: &[WordPart],
+        fields: &mut Fields,
+        quoted: bool,
+        io: &Io,
+    ) -> Result<(), NeocashError> {
+        for (i, part) in parts.iter().enumerate() {
+            match part {
+                WordPart::Literal(text) if i == 0 && !quoted && text.starts_with('~') => {
+                    match self.expand_tilde(text) {
+                        // The home directory itself is never a pattern
+                        Some((home, rest)) => {
+                            fields.push_quoted(&home);
+                            fields.push_str(rest);
+                        }
+                        None => fields.push_str(text),
+                    }
+                }
+                WordPart::Literal(text) if quoted => fields.push_quoted(text),
+                WordPart::Literal(text) => fields.push_str(text),
+                WordPart::Quoted(text) => fields.push_quoted(text),
+                WordPart::DoubleQuoted(inner) => {
+                    fields.push_quoted("");
+                    self.expand_parts(inner, fields, true, io)?;
+                }
+                WordPart::Param(param) => self.expand_param(param, fields, quoted, io)?,
+                WordPart::CommandSubst(list) => {
+                    let output = self.command_substitution(list, io)?;
+                    fields.push_expansion(&output, quoted);
+                }
+                WordPart::Arith(expr) => {
+                    let mut text = Fields::new(None);
+                    self.expand_parts(expr, &mut text, true, io)?;
+                    let value = arith::evaluate(&text.join(), &mut self.state.vars)
+                        .map_err(|e| self.fatal(e))?;
+                    fields.push_expansion(&value.to_string(), quoted);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// An expansion error a script cannot go on from, as it would run with a
+    /// value it does not have. An interactive shell only abandons the command.
+    fn fatal(&mut self, error: impl Into<NeocashError>) -> NeocashError {
+        if !self.interactive {
+            self.exit_status.get_or_insert(1);
+        }
+        error.into()
+    }
+
+    fn expand_param(
+        &mut self,
+        param: &ParamExpansion,
+        fields: &mut Fields,
+        quoted: bool,
+        io: &Io,
+    ) -> Result<(), NeocashError> {
+        if param.name == "@" && param.op.is_none() && quoted {
+            fields.push_each(&self.positional);
+            return Ok(());
+        }
+
+        let value = self.get_var(&param.name);
+        if value.is_none() && self.state.options.nounset && !handles_unset(param) {
+            return Err(self.fatal(format!("{}: unbound variable", param.name)));
+        }
+        let Some(op) = &param.op else {
+            fields.push_expansion(&value.unwrap_or_default(), quoted);
+            return Ok(());
+        };
+
+        // With a colon an empty value counts as unset
+        let is_set = value.is_some();
+        let is_non_empty = value.as_deref().is_some_and(|v| !v.is_empty());
+        let present = |colon: bool| if colon { is_non_empty } else { is_set };
+        let value = value.unwrap_or_default();
+
+        let result = match op {
+            ParamOp::Length => match param
+                .name
+                .strip_suffix("[@]")
+                .or_else(|| param.name.strip_suffix("[*]"))
+            {
+                Some(name) => self.array(name).len().to_string(),
+                None => value.chars().count().to_string(),
+            },
+            ParamOp::Default { colon, word } => {
+                if !present(*colon) {
+                    return self.expand_parts(&word.parts, fields, quoted, io);
+                }
+                value
+            }
+            ParamOp::Alternative { colon, word } => {
+                if present(*colon) {
+                    return self.expand_parts(&word.parts, fields, quoted, io);
+                }
+                String::new()
+            }
+            ParamOp::Assign { colon, word } => {
+                if present(*colon) {
+                    value
+                } else if !is_name(&param.name) {
+                    return Err(format!("${}: cannot assign in this way", param.name).into());
+                } else {
+                    let value = self.expand_word(word, io)?;
+                    self.state.vars.set(&param.name, value.clone());
+                    value
+                }
+            }
+            ParamOp::Error { colon, word } => {
+                if !present(*colon) {
+                    let message = match self.expand_word(word, io)? {
+                        message if message.is_empty() => "parameter null or not set".to_string(),
+                        message => message,
+                    };
+                    return Err(self.fatal(format!("{}: {}", param.name, message)));
+                }
+                value
+            }
+            ParamOp::RemovePrefix { longest, pattern } => {
+                let pattern = Pattern::new(&self.expand_pattern(pattern, io)?);
+                remove_prefix(&value, &pattern, *longest)
+            }
+            ParamOp::RemoveSuffix { longest, pattern } => {
+                let pattern = Pattern::new(&self.expand_pattern(pattern, io)?);
+                remove_suffix(&value, &pattern, *longest)
+            }
+            ParamOp::Replace {
+                mode,
+                pattern,
+                replacement,
+            } => {
+                let pattern = self.expand_pattern(pattern, io)?;
+                let replacement = self.expand_word(replacement, io)?;
+                if pattern.is_empty() && matches!(mode, ReplaceMode::First | ReplaceMode::All) {
+                    value
+                } else {
+                    replace(&value, &Pattern::new(&pattern), &replacement, *mode)
+                }
+            }
+        };
+
+        fields.push_expansion(&result, quoted);
+        Ok(())
+    }
+
+    /// Runs `list` in a subshell and returns what it wrote to standard
+    /// output, without trailing newlines. The other descriptors are those
+    /// of `io`.
+    fn command_substitution(&mut self, list: &List, io: &Io) -> Result<String, NeocashError> {
+        let (mut reader, writer) = std::io::pipe().map_err(|e| NeocashError::io("pipe", e))?;
+        let mut sub_io = io.clone();
+        sub_io.set(1, writer);
+
+        // The parent's copy of the write end is dropped with `sub_io`, so
+        // that reading sees end-of-file once the subshell exits
+        let result = fork_with(None, || {
+            self.enter_subshell();
+            // Unlike the interactive shell, the subshell can be interrupted
+            signals::restore_default_signals().ok();
+            let status = self.execute_body(list, &sub_io);
+            self.state.last_status = status;
+            self.finish(status, &sub_io)
+        });
+        drop(sub_io);
+        let pid = result.map_err(|e| NeocashError::io("fork", e))?;
+
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).ok();
+        let status = wait_pid(pid, false).ok().and_then(exit_status).unwrap_or(1);
+        self.substitution_status = Some(status);
+
+        let mut output = String::from_utf8_lossy(&output).into_owned();
+        output.truncate(output.trim_end_matches('\n').len());
+        Ok(output)
+    }
+
+    /// Splits a leading `~` or `~user` off `text` and returns the home
+    /// directory it stands for along with the rest of the text
+    fn expand_tilde<'t>(&self, text: &'t str) -> Option<(String, &'t str)> {
+        let (prefix, rest) = match text.find('/') {
+            Some(slash) => text.split_at(slash),
+            None => (text, ""),
+        };
+
+        let home = match &prefix[1..] {
+            "" => self
+                .get_var("HOME")
+                .or_else(|| dirs::home_dir().map(|p| p.display().to_string())),
+            user => User::from_name(user)
+                .ok()
+                .flatten()
+                .map(|user| user.dir.display().to_string()),
+        };
+
+        home.map(|home| (home, rest))
+    }
+}
+
+/// Whether an expansion is fine with an unset parameter under `nounset`
+fn handles_unset(param: &ParamExpansion) -> bool {
+    matches!(param.name.as_str(), "@" | "*")
+        || matches!(
+            param.op,
+            Some(
+                ParamOp::Default { .. }
+                    | ParamOp::Alternative { .. }
+                    | ParamOp::Assign { .. }
+                    | ParamOp::Error { .. }
+            )
+        )
+}
+
+/// Byte offsets of every character boundary in `s`, including both ends
+fn boundaries(s: &str) -> Vec<usize> {
+    s.char_indices().map(|(i, _)| i).chain([s.len()]).collect()
+}
+
+/// `${var#pattern}` and `${var##pattern}`
+fn remove_prefix(value: &str, pattern: &Pattern, longest: bool) -> String {
+    let mut ends = boundaries(value);
+    if longest {
+        ends.reverse();
+    }
+    match ends.into_iter().find(|&end| pattern.matches(&value[..end])) {
+        Some(end) => value[end..].to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// `${var%pattern}` and `${var%%pattern}`
+fn remove_suffix(value: &str, pattern: &Pattern, longest: bool) -> String {
+    let mut starts = boundaries(value);
+    if !longest {
+        starts.reverse();
+    }
+    match starts
+        .into_iter()
+        .find(|&start| pattern.matches(&value[start..]))
+    {
+        Some(start) => value[..start].to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// `${var/pattern/replacement}`. Matches are always the longest possible.
+fn replace(value: &str, pattern: &Pattern, replacement: &str, mode: ReplaceMode) -> String {
+    let bounds = boundaries(value);
+    match mode {
+        ReplaceMode::Prefix => match bounds
+            .iter()
+            .rev()
+            .find(|&&end| pattern.matches(&value[..end]))
+        {
+            Some(&end) => format!("{}{}", replacement, &value[end..]),
+            None => value.to_string(),
+        },
+        ReplaceMode::Suffix => match bounds
+            .iter()
+            .find(|&&start| pattern.matches(&value[start..]))
+        {
+            Some(&start) => format!("{}{}", &value[..start], replacement),
+            None => value.to_string(),
+        },
+        ReplaceMode::First | ReplaceMode::All => {
+            let mut result = String::new();
+            let mut i = 0;
+            while i + 1 < bounds.len() {
+                let start = bounds[i];
+                let end = bounds[i + 1..]
+                    .iter()
+                    .rposition(|&end| pattern.matches(&value[start..end]));
+                let Some(end) = end.map(|j| i + 1 + j) else {
+                    result.push_str(&value[start..bounds[i + 1]]);
+                    i += 1;
+                    continue;
+                };
+                result.push_str(replacement);
+                i = end;
+                if mode == ReplaceMode::First {
+                    break;
+                }
+            }
+            result.push_str(&value[bounds[i]..]);
+            result
+        }
+    }
+}
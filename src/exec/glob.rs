@@ -0,0 +1,210 @@
+// exec/glob.rs
+
+use crate::exec::Pattern;
+use std::fs;
+use std::path::Path;
+
+/// Expands a pathname pattern into the sorted list of matching paths.
+/// Quoted characters in `pattern` are escaped with a backslash. A `**`
+/// component matches any number of directories. Names starting with `.`
+/// are only matched by a component that starts with a literal `.`, unless
+/// `dotglob` is set. Relative patterns are matched in `cwd`.
+pub fn glob(pattern: &str, cwd: &Path, dotglob: bool) -> Vec<String> {
+    let (root, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => ("/", rest),
+        None => ("", pattern),
+    };
+    let dirs_only = rest.ends_with('/');
+    let components: Vec<&str> = rest.split('/').filter(|c| !c.is_empty()).collect();
+
+    let mut paths = vec![root.to_string()];
+    for (i, component) in components.iter().enumerate() {
+        let last = i + 1 == components.len();
+        let mut next = Vec::new();
+
+        for base in &paths {
+            if *component == "**" {
+                if !last {
+                    // `**` may also match no directory at all
+                    next.push(base.clone());
+                }
+                walk(cwd, base, dotglob, !last, &mut next);
+                continue;
+            }
+
+            let matcher = Pattern::new(component);
+            if matcher.is_literal() {
+                let path = join(base, &unescape(component));
+                let exists = if last {
+                    fs::symlink_metadata(cwd.join(&path)).is_ok()
+                } else {
+                    cwd.join(&path).is_dir()
+                };
+                if exists {
+                    next.push(path);
+                }
+                continue;
+            }
+
+            let show_hidden = dotglob || component.starts_with('.');
+            for name in entries(cwd, base) {
+                if name.starts_with('.') && !show_hidden || !matcher.matches(&name) {
+                    continue;
+                }
+                let path = join(base, &name);
+                if last || cwd.join(&path).is_dir() {
+                    next.push(path);
+                }
+            }
+        }
+        paths = next;
+    }
+
+    if components.is_empty() {
+        return Vec::new();
+    }
+    if dirs_only {
+        paths.retain(|path| cwd.join(path).is_dir());
+        for path in &mut paths {
+            path.push('/');
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Adds every entry below `base` to `paths`, or only directories with
+/// `dirs_only`. Symbolic links to directories are not followed.
+fn walk(cwd: &Path, base: &str, dotglob: bool, dirs_only: bool, paths: &mut Vec<String>) {
+    for name in entries(cwd, base) {
+        if name.starts_with('.') && !dotglob {
+            continue;
+        }
+        let path = join(base, &name);
+        let is_dir = fs::symlink_metadata(cwd.join(&path)).is_ok_and(|m| m.is_dir());
+        if is_dir || !dirs_only {
+            paths.push(path.clone());
+        }
+        if is_dir {
+            walk(cwd, &path, dotglob, dirs_only, paths);
+        }
+    }
+}
+
+/// Names in the directory `base`, which is `cwd` itself if empty
+fn entries(cwd: &Path, base: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(cwd.join(base)) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect()
+}
+
+fn join(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else if base.ends_with('/') {
+        format!("{}{}", base, name)
+    } else {
+        format!("{}/{}", base, name)
+    }
+}
+
+/// Removes the backslashes that escape characters in a pattern
+fn unescape(pattern: &str) -> String {
+    let mut text = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A directory tree of its own for each test
+    fn tree(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("neocash-glob-{}-{}", name, std::process::id()));
+        for sub in ["src/lib", "src/.cache", "docs"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        for file in [
+            "a.rs",
+            "b.txt",
+            ".hidden.rs",
+            "we*ird",
+            "src/main.rs",
+            "src/lib/mod.rs",
+            "src/.cache/x.rs",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn matches_names_in_a_directory() {
+        let dir = tree("names");
+        assert_eq!(glob("*.rs", &dir, false), ["a.rs"]);
+        assert_eq!(glob("[ab].*", &dir, false), ["a.rs", "b.txt"]);
+        assert_eq!(glob("src/*.rs", &dir, false), ["src/main.rs"]);
+        assert_eq!(glob("*/*.rs", &dir, false), ["src/main.rs"]);
+        assert!(glob("*.md", &dir, false).is_empty());
+
+        let absolute = format!("{}/*.txt", dir.display());
+        assert_eq!(
+            glob(&absolute, Path::new("/"), false),
+            [format!("{}/b.txt", dir.display())]
+        );
+    }
+
+    #[test]
+    fn hides_dot_files_unless_asked() {
+        let dir = tree("dot");
+        assert_eq!(glob(".*.rs", &dir, false), [".hidden.rs"]);
+        assert_eq!(glob("*.rs", &dir, true), [".hidden.rs", "a.rs"]);
+        assert_eq!(
+            glob("src/**/*.rs", &dir, true),
+            ["src/.cache/x.rs", "src/lib/mod.rs", "src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn matches_any_depth_with_double_star() {
+        let dir = tree("double-star");
+        assert_eq!(
+            glob("**/*.rs", &dir, false),
+            ["a.rs", "src/lib/mod.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            glob("src/**", &dir, false),
+            ["src/lib", "src/lib/mod.rs", "src/main.rs"]
+        );
+        assert_eq!(glob("**/lib", &dir, false), ["src/lib"]);
+    }
+
+    #[test]
+    fn keeps_only_directories_before_a_trailing_slash() {
+        let dir = tree("slash");
+        assert_eq!(glob("*/", &dir, false), ["docs/", "src/"]);
+        assert_eq!(glob("src/*/", &dir, false), ["src/lib/"]);
+    }
+
+    #[test]
+    fn matches_escaped_characters_literally() {
+        let dir = tree("escaped");
+        assert_eq!(glob("we\\*ird", &dir, false), ["we*ird"]);
+        assert_eq!(glob("we*", &dir, false), ["we*ird"]);
+        assert!(glob("we\\*", &dir, false).is_empty());
+    }
+}
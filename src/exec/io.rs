@@ -0,0 +1,115 @@
+// exec/io.rs
+
+use crate::error::{NeocashError, use_color};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Write};
+use std::os::fd::{AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::process::Stdio;
+use std::rc::Rc;
+
+/// The file descriptors a command runs with. Descriptors that are not set
+/// are inherited from the shell itself; `None` marks a closed descriptor.
+#[derive(Clone, Default)]
+pub struct Io {
+    fds: BTreeMap<i32, Option<Rc<OwnedFd>>>,
+}
+
+impl Io {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, fd: i32, file: impl Into<OwnedFd>) {
+        self.fds.insert(fd, Some(Rc::new(file.into())));
+    }
+
+    /// Makes `fd` refer to the same open file as another descriptor
+    pub fn set_shared(&mut self, fd: i32, file: Rc<OwnedFd>) {
+        self.fds.insert(fd, Some(file));
+    }
+
+    pub fn close(&mut self, fd: i32) {
+        self.fds.insert(fd, None);
+    }
+
+    pub fn get(&self, fd: i32) -> Option<&Rc<OwnedFd>> {
+        self.fds.get(&fd).and_then(Option::as_ref)
+    }
+
+    /// Returns the open file `fd` currently refers to, duplicating the
+    /// shell's own descriptor if it is inherited
+    pub fn resolve(&self, fd: i32) -> io::Result<Rc<OwnedFd>> {
+        match self.fds.get(&fd) {
+            Some(Some(file)) => Ok(Rc::clone(file)),
+            Some(None) => Err(io::Error::from_raw_os_error(nix::libc::EBADF)),
+            None => {
+                // SAFETY: the descriptor is only borrowed long enough to duplicate
+                // it; an invalid descriptor makes the duplication fail with EBADF.
+                let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+                Ok(Rc::new(borrowed.try_clone_to_owned()?))
+            }
+        }
+    }
+
+    /// Returns the descriptor as a `Stdio` suitable for a child process.
+    /// A closed descriptor is given to the child as `/dev/null`.
+    pub fn stdio(&self, fd: i32) -> io::Result<Stdio> {
+        match self.fds.get(&fd) {
+            Some(Some(file)) => Ok(Stdio::from(file.try_clone()?)),
+            Some(None) => Ok(Stdio::null()),
+            None => Ok(Stdio::inherit()),
+        }
+    }
+
+    /// Descriptors above 2 that a child process has to `dup2` into place,
+    /// as `(source, target)` pairs
+    pub fn extra_fds(&self) -> Vec<(RawFd, RawFd)> {
+        self.fds
+            .range(3..)
+            .filter_map(|(&fd, file)| file.as_ref().map(|file| (file.as_raw_fd(), fd)))
+            .collect()
+    }
+
+    fn file(&self, fd: i32) -> Option<Option<File>> {
+        self.fds.get(&fd).map(|file| {
+            file.as_ref()
+                .and_then(|file| file.try_clone().ok())
+                .map(File::from)
+        })
+    }
+
+    /// Reports an error on the standard error of a command, colored when
+    /// that is a terminal and `no_color` (the value of `NO_COLOR`) is empty
+    pub fn report(&self, error: &NeocashError, command: Option<&str>, no_color: Option<&str>) {
+        let terminal = self.resolve(2).is_ok_and(|fd| fd.is_terminal());
+        error.write_to(&mut self.stderr(), use_color(terminal, no_color), command);
+    }
+
+    /// Standard input for builtins
+    pub fn stdin(&self) -> Box<dyn Read> {
+        match self.file(0) {
+            Some(Some(file)) => Box::new(file),
+            Some(None) => Box::new(io::empty()),
+            None => Box::new(io::stdin()),
+        }
+    }
+
+    /// Standard output for builtins
+    pub fn stdout(&self) -> Box<dyn Write> {
+        match self.file(1) {
+            Some(Some(file)) => Box::new(file),
+            Some(None) => Box::new(io::sink()),
+            None => Box::new(io::stdout()),
+        }
+    }
+
+    /// Standard error for builtins
+    pub fn stderr(&self) -> Box<dyn Write> {
+        match self.file(2) {
+            Some(Some(file)) => Box::new(file),
+            Some(None) => Box::new(io::sink()),
+            None => Box::new(io::stderr()),
+        }
+    }
+}
@@ -0,0 +1,450 @@
+// exec/job.rs
+
+use crate::error::NeocashError;
+use crate::exec::process::{exit_status, wait_pid};
+use crate::exec::{Executor, Io};
+use crate::signals;
+use nix::errno::Errno;
+use nix::sys::signal::{Signal, killpg};
+use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
+use nix::unistd::{Pid, getpgrp, getpid, isatty, setpgid, tcgetpgrp, tcsetpgrp};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessState {
+    Running,
+    Stopped,
+    Done(i32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: Pid,
+    pub state: ProcessState,
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: usize,
+    pub pgid: Pid,
+    pub command: String,
+    pub processes: Vec<Process>,
+    /// Whether the current state has been reported to the user
+    pub notified: bool,
+}
+
+impl Job {
+    pub fn state(&self) -> ProcessState {
+        let states = || self.processes.iter().map(|p| p.state);
+        if states().any(|s| s == ProcessState::Running) {
+            ProcessState::Running
+        } else if states().any(|s| s == ProcessState::Stopped) {
+            ProcessState::Stopped
+        } else {
+            states().next_back().unwrap_or(ProcessState::Done(0))
+        }
+    }
+
+    /// Exit status of the job as seen by `$?`
+    pub fn status(&self) -> i32 {
+        match self.state() {
+            ProcessState::Done(code) => code,
+            ProcessState::Stopped => 128 + Signal::SIGTSTP as i32,
+            ProcessState::Running => 0,
+        }
+    }
+
+    /// Formats the job the way `jobs` lists it
+    pub fn describe(&self, marker: char) -> String {
+        let (state, suffix) = match self.state() {
+            ProcessState::Running => ("Running".to_string(), " &"),
+            ProcessState::Stopped => ("Stopped".to_string(), ""),
+            ProcessState::Done(0) => ("Done".to_string(), ""),
+            ProcessState::Done(code) => (format!("Exit {}", code), ""),
+        };
+        format!(
+            "[{}]{}  {:<24}{}{}",
+            self.id, marker, state, self.command, suffix
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct JobTable {
+    jobs: Vec<Job>,
+    /// Job ids from least to most recently used; the last one is the
+    /// current job (`%+`) and the one before it the previous job (`%-`)
+    recent: Vec<usize>,
+}
+
+impl JobTable {
+    pub fn add(&mut self, pgid: Pid, command: String, processes: Vec<Process>) -> usize {
+        let id = self.jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        self.jobs.push(Job {
+            id,
+            pgid,
+            command,
+            processes,
+            notified: false,
+        });
+        self.set_current(id);
+        id
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        self.recent.retain(|&recent| recent != id);
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        Some(self.jobs.remove(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    pub fn ids(&self) -> Vec<usize> {
+        self.jobs.iter().map(|job| job.id).collect()
+    }
+
+    pub fn set_current(&mut self, id: usize) {
+        self.recent.retain(|&recent| recent != id);
+        self.recent.push(id);
+    }
+
+    /// `+` for the current job, `-` for the previous one
+    pub fn marker(&self, id: usize) -> char {
+        match self.recent.iter().rev().position(|&recent| recent == id) {
+            Some(0) => '+',
+            Some(1) => '-',
+            _ => ' ',
+        }
+    }
+
+    /// Resolves a job specification such as `%1`, `%+`, `%-`, `%vim` or
+    /// `%?make`. Without a specification the current job is used.
+    pub fn resolve(&self, spec: Option<&str>) -> Result<usize, NeocashError> {
+        let spec = spec.unwrap_or("%+");
+        let key = spec.strip_prefix('%').unwrap_or(spec);
+
+        let found = match key {
+            "" | "+" | "%" => self.recent.last().copied(),
+            "-" => self.recent.iter().rev().nth(1).copied(),
+            _ => {
+                if let Ok(id) = key.parse::<usize>() {
+                    self.get(id).map(|job| job.id)
+                } else if let Some(needle) = key.strip_prefix('?') {
+                    self.find(|job| job.command.contains(needle))?
+                } else {
+                    self.find(|job| job.command.starts_with(key))?
+                }
+            }
+        };
+
+        found.ok_or_else(|| NeocashError::NoSuchJob(spec.to_string()))
+    }
+
+    fn find(&self, pred: impl Fn(&Job) -> bool) -> Result<Option<usize>, NeocashError> {
+        let mut matches = self.jobs.iter().filter(|job| pred(job));
+        match (matches.next(), matches.next()) {
+            (Some(job), None) => Ok(Some(job.id)),
+            (None, _) => Ok(None),
+            (Some(job), Some(_)) => Err(NeocashError::invalid(&job.command, "ambiguous job spec")),
+        }
+    }
+
+    /// Records a new state for the process with the given pid
+    pub fn update(&mut self, pid: Pid, state: ProcessState) {
+        for job in &mut self.jobs {
+            if let Some(process) = job.processes.iter_mut().find(|p| p.pid == pid) {
+                if process.state != state {
+                    process.state = state;
+                    job.notified = false;
+                }
+                return;
+            }
+        }
+    }
+
+    /// Collects status changes of all children without blocking
+    pub fn reap(&mut self) {
+        let flags = WaitPidFlag::WNOHANG | WaitPidFlag::WUNTRACED | WaitPidFlag::WCONTINUED;
+        loop {
+            match waitpid(Pid::from_raw(-1), Some(flags)) {
+                Ok(WaitStatus::StillAlive) | Err(Errno::ECHILD) => break,
+                Ok(WaitStatus::Stopped(pid, _)) => self.update(pid, ProcessState::Stopped),
+                Ok(WaitStatus::Continued(pid)) => self.update(pid, ProcessState::Running),
+                Ok(status) => {
+                    if let (Some(pid), Some(code)) = (status.pid(), exit_status(status)) {
+                        self.update(pid, ProcessState::Done(code));
+                    }
+                }
+                Err(Errno::EINTR) => continue,
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Returns status lines for jobs that stopped or finished since the last
+    /// call and forgets finished jobs
+    pub fn take_notifications(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for id in self.ids() {
+            let marker = self.marker(id);
+            let Some(job) = self.get_mut(id) else {
+                continue;
+            };
+            let state = job.state();
+            if job.notified || state == ProcessState::Running {
+                continue;
+            }
+            job.notified = true;
+            lines.push(job.describe(marker));
+            if let ProcessState::Done(_) = state {
+                self.remove(id);
+            }
+        }
+        lines
+    }
+}
+
+impl Executor {
+    /// Puts the shell in its own process group and takes over the terminal.
+    /// Returns `false` when stdin is not a terminal, in which case job
+    /// control stays disabled.
+    pub fn enable_job_control(&mut self) -> bool {
+        if !isatty(0).unwrap_or(false) {
+            return false;
+        }
+
+        // Wait until we are in the foreground before taking the terminal
+        while tcgetpgrp(0).is_ok_and(|pgrp| pgrp != getpgrp()) {
+            killpg(getpgrp(), Signal::SIGTTIN).ok();
+        }
+
+        signals::ignore_interactive_signals();
+
+        let pid = getpid();
+        setpgid(pid, pid).ok();
+        if tcsetpgrp(0, pid).is_err() {
+            return false;
+        }
+
+        self.shell_pgid = Some(pid);
+        true
+    }
+
+    /// Whether this shell manages process groups and the terminal
+    pub fn job_control(&self) -> bool {
+        self.shell_pgid.is_some()
+    }
+
+    /// Prints notifications about jobs that finished or stopped since the
+    /// last prompt
+    pub fn notify_jobs(&mut self, io: &Io) {
+        self.state.jobs.reap();
+        for line in self.state.jobs.take_notifications() {
+            writeln!(io.stderr(), "{}", line).ok();
+        }
+    }
+
+    /// Warns about running and stopped jobs the first time `exit` is used
+    /// while there are any. Returns whether the shell may exit.
+    pub fn confirm_exit(&mut self, io: &Io) -> bool {
+        if !self.job_control() || self.exit_warned {
+            return true;
+        }
+        self.state.jobs.reap();
+        let states: Vec<_> = self.state.jobs.iter().map(Job::state).collect();
+        let kind = if states.contains(&ProcessState::Stopped) {
+            "stopped"
+        } else if states.contains(&ProcessState::Running) {
+            "running"
+        } else {
+            return true;
+        };
+        writeln!(io.stderr(), "There are {} jobs.", kind).ok();
+        self.exit_warned = true;
+        false
+    }
+
+    /// Hands the terminal back to the shell after a foreground job
+    pub(crate) fn reclaim_terminal(&self) {
+        if let Some(pgid) = self.shell_pgid {
+            tcsetpgrp(0, pgid).ok();
+        }
+    }
+
+    /// Waits for the processes of a foreground job. If the job is stopped it
+    /// is added to the job table (or kept there, for a job resumed with `fg`).
+    /// Returns the exit status of every process.
+    pub(crate) fn wait_foreground(
+        &mut self,
+        processes: Vec<Process>,
+        job_id: Option<usize>,
+        io: &Io,
+    ) -> Vec<i32> {
+        let mut processes = processes;
+        let untraced = self.job_control();
+        let mut interrupted = false;
+
+        for process in processes
+            .iter_mut()
+            .filter(|p| !matches!(p.state, ProcessState::Done(_)))
+        {
+            process.state = match wait_pid(process.pid, untraced) {
+                Ok(WaitStatus::Stopped(..)) => ProcessState::Stopped,
+                Ok(status) => {
+                    interrupted |= matches!(status, WaitStatus::Signaled(_, Signal::SIGINT, _));
+                    ProcessState::Done(exit_status(status).unwrap_or(1))
+                }
+                Err(_) => ProcessState::Done(1),
+            };
+        }
+        self.reclaim_terminal();
+        // A `^C` that reached the shell while the job was being set up
+        interrupted |= signals::take_interrupt();
+
+        // Start the next prompt on a fresh line after `^C`
+        if interrupted && untraced {
+            writeln!(io.stderr()).ok();
+        }
+        // A trapped `SIGINT` runs the trap instead of aborting the commands
+        if !self.state.traps.contains_key("INT") {
+            self.interrupted |= interrupted;
+        }
+
+        let stopped_stage = 128 + Signal::SIGTSTP as i32;
+        let statuses = processes
+            .iter()
+            .map(|p| match p.state {
+                ProcessState::Done(code) => code,
+                _ => stopped_stage,
+            })
+            .collect();
+
+        let stopped = processes.iter().any(|p| p.state == ProcessState::Stopped);
+        if let Some(id) = job_id {
+            if stopped {
+                if let Some(job) = self.state.jobs.get_mut(id) {
+                    job.processes = processes;
+                    job.notified = true;
+                }
+                self.state.jobs.set_current(id);
+                self.print_stopped(id, io);
+            } else {
+                self.state.jobs.remove(id);
+            }
+        } else if stopped {
+            let pgid = processes[0].pid;
+            let id = self
+                .state
+                .jobs
+                .add(pgid, self.current_command.clone(), processes);
+            if let Some(job) = self.state.jobs.get_mut(id) {
+                job.notified = true;
+            }
+            self.print_stopped(id, io);
+        }
+
+        statuses
+    }
+
+    fn print_stopped(&self, id: usize, io: &Io) {
+        if let Some(job) = self.state.jobs.get(id) {
+            let description = job.describe(self.state.jobs.marker(id));
+            writeln!(io.stderr(), "\n{}", description).ok();
+        }
+    }
+
+    /// Continues a job in the foreground and waits for it (`fg`). Its
+    /// command is printed to the standard output of `io`.
+    pub(crate) fn foreground_job(&mut self, id: usize, io: &Io) -> Result<i32, NeocashError> {
+        let job = self
+            .state
+            .jobs
+            .get(id)
+            .ok_or_else(|| no_such_job(id))?
+            .clone();
+        writeln!(io.stdout(), "{}", job.command)?;
+
+        if self.job_control() {
+            tcsetpgrp(0, job.pgid)?;
+        }
+        killpg(job.pgid, Signal::SIGCONT)?;
+
+        let processes = job
+            .processes
+            .into_iter()
+            .map(|p| match p.state {
+                ProcessState::Stopped => Process {
+                    state: ProcessState::Running,
+                    ..p
+                },
+                _ => p,
+            })
+            .collect();
+        self.current_command = job.command;
+        let statuses = self.wait_foreground(processes, Some(id), io);
+        Ok(statuses.last().copied().unwrap_or(0))
+    }
+
+    /// Continues a stopped job in the background (`bg`), printing it to the
+    /// standard output of `io`
+    pub(crate) fn background_job(&mut self, id: usize, io: &Io) -> Result<(), NeocashError> {
+        let job = self.state.jobs.get_mut(id).ok_or_else(|| no_such_job(id))?;
+        killpg(job.pgid, Signal::SIGCONT)?;
+        for process in &mut job.processes {
+            if process.state == ProcessState::Stopped {
+                process.state = ProcessState::Running;
+            }
+        }
+        self.state.jobs.set_current(id);
+
+        let job = self.state.jobs.get(id).ok_or_else(|| no_such_job(id))?;
+        writeln!(
+            io.stdout(),
+            "[{}]{} {} &",
+            job.id,
+            self.state.jobs.marker(id),
+            job.command
+        )?;
+        Ok(())
+    }
+
+    /// Blocks until a job finishes and returns its status (`wait`)
+    pub(crate) fn wait_job(&mut self, id: usize) -> i32 {
+        let Some(job) = self.state.jobs.get(id).cloned() else {
+            return 127;
+        };
+
+        for process in &job.processes {
+            if process.state != ProcessState::Running {
+                continue;
+            }
+            let state = match wait_pid(process.pid, false) {
+                Ok(status) => ProcessState::Done(exit_status(status).unwrap_or(1)),
+                Err(_) => ProcessState::Done(127),
+            };
+            self.state.jobs.update(process.pid, state);
+        }
+
+        let status = self.state.jobs.get(id).map(Job::status).unwrap_or(127);
+        self.state.jobs.remove(id);
+        status
+    }
+}
+
+fn no_such_job(id: usize) -> NeocashError {
+    NeocashError::NoSuchJob(format!("%{}", id))
+}
@@ -0,0 +1,520 @@
+// exec/mod.rs
+
+mod arith;
+mod brace;
+mod compound;
+mod expand;
+mod glob;
+mod io;
+mod job;
+mod options;
+mod pattern;
+mod pipeline;
+mod process;
+mod redirect;
+mod script;
+mod state;
+mod trap;
+mod vars;
+
+pub use compound::LoopControl;
+pub use io::Io;
+pub use job::{Job, JobTable, Process, ProcessState};
+pub use options::ShellOptions;
+pub use pattern::Pattern;
+pub use process::find_in_path;
+pub use script::stdin_lines;
+pub use state::ShellState;
+pub use trap::TrapCondition;
+pub use vars::{Variable, Variables};
+
+pub(crate) use redirect::temp_file;
+
+use crate::commands::Builtins;
+use crate::config::ShellConfig;
+use crate::error::NeocashError;
+use crate::signals;
+use crate::syntax::{self, AndOr, AndOrOp, Command, List, Pipeline, SimpleCommand};
+use nix::unistd::{Pid, getpid};
+use process::ProcessGroup;
+use std::os::unix::process::CommandExt;
+use std::rc::Rc;
+
+pub struct Executor {
+    pub config: ShellConfig,
+    /// Working directory, variables, functions and everything else the
+    /// commands run by this shell can change
+    pub state: ShellState,
+    /// Pid of the most recent background job
+    pub last_background_pid: Option<Pid>,
+    /// Value of `$0`
+    pub arg0: String,
+    /// `$1`, `$2`, ... of the script or function being executed
+    pub positional: Vec<String>,
+    /// Builtins by name, which embedders can extend
+    pub builtins: Builtins,
+    /// Number of function calls in progress
+    pub function_depth: usize,
+    /// Number of files being run by `source`
+    pub source_depth: usize,
+    /// Set by `return` until the function or sourced file it leaves has
+    /// finished
+    pub return_status: Option<i32>,
+    /// Set by `exit`; the shell stops once the commands in progress have
+    /// been abandoned
+    pub exit_status: Option<i32>,
+    /// Whether this is a login shell, which runs the logout script on exit
+    pub login: bool,
+    /// Whether commands are read from a terminal
+    pub interactive: bool,
+    /// Whether a trap action is running, during which `ERR` is not trapped
+    in_trap: bool,
+    /// Number of conditions being tested, like that of an `if` or the left
+    /// side of `&&`, where a failing command is not an error
+    condition_depth: usize,
+    /// Number of loops the command being executed is nested in
+    pub loop_depth: usize,
+    /// Set by `break` and `continue` until the enclosing loop handles it
+    pub loop_control: Option<LoopControl>,
+    /// Set when `exit` warned about jobs, so that a second `exit` in a row
+    /// leaves them behind
+    exit_warned: bool,
+    /// Set when a foreground command is killed by `SIGINT`, which aborts
+    /// the rest of the command line
+    interrupted: bool,
+    /// Status of the last command substitution in the command being
+    /// expanded, which becomes the status of a command without a name
+    substitution_status: Option<i32>,
+    /// Pid of the shell itself, which `$$` keeps reporting in subshells
+    shell_pid: Pid,
+    /// Process group of the shell while job control is enabled
+    shell_pgid: Option<Pid>,
+    /// Process group that processes started for the current job join
+    launch: Option<ProcessGroup>,
+    /// Text of the job being run in the foreground, for the job table
+    current_command: String,
+}
+
+impl Executor {
+    /// Creates a shell started from the environment and working directory
+    /// of this process
+    pub fn new(config: ShellConfig) -> Self {
+        Self::with_state(config, ShellState::from_env())
+    }
+
+    /// Creates a shell with its own state, independent of the process and
+    /// of other shells. The aliases and abbreviations of `config` are added,
+    /// and the options it turns on are set.
+    pub fn with_state(config: ShellConfig, mut state: ShellState) -> Self {
+        state.aliases.extend(config.aliases.clone());
+        state.abbreviations.extend(config.abbreviations.clone());
+        for (name, _) in config.options.iter().filter(|(_, on)| *on) {
+            state.options.set(name, true).ok();
+        }
+        Self {
+            config,
+            state,
+            last_background_pid: None,
+            arg0: "neocash".to_string(),
+            positional: Vec::new(),
+            builtins: Builtins::default(),
+            function_depth: 0,
+            source_depth: 0,
+            return_status: None,
+            exit_status: None,
+            login: false,
+            interactive: false,
+            in_trap: false,
+            condition_depth: 0,
+            loop_depth: 0,
+            loop_control: None,
+            exit_warned: false,
+            interrupted: false,
+            substitution_status: None,
+            shell_pid: getpid(),
+            shell_pgid: None,
+            launch: None,
+            current_command: String::new(),
+        }
+    }
+
+    /// Parses and executes a command line, returning its exit status
+    pub fn execute_line(&mut self, line: &str) -> i32 {
+        // A warning from `exit` only holds until the next command line
+        let warned = std::mem::take(&mut self.exit_warned);
+        match syntax::parse_with_aliases(line, &self.state.aliases) {
+            Ok(list) => {
+                self.exit_warned = warned;
+                let status = self.execute_list(&list);
+                if warned {
+                    self.exit_warned = false;
+                }
+                status
+            }
+            Err(e) => {
+                self.report(&Io::new(), &NeocashError::Parse(e), None);
+                self.state.last_status = 2;
+                2
+            }
+        }
+    }
+
+    /// Executes a parsed command line and returns the status of the last
+    /// command that ran
+    pub fn execute_list(&mut self, list: &List) -> i32 {
+        self.clear_interrupt();
+        let mut status = self.execute_body(list, &Io::new());
+        if self.interrupted {
+            status = 130;
+        }
+        self.state.last_status = status;
+        status
+    }
+
+    /// Looks up a variable or special parameter
+    pub fn get_var(&self, name: &str) -> Option<String> {
+        match name {
+            "?" => Some(self.state.last_status.to_string()),
+            "$" => Some(self.shell_pid.to_string()),
+            "-" => {
+                let interactive = if self.interactive { "i" } else { "" };
+                Some(format!("{}{}", self.state.options.flags(), interactive))
+            }
+            "!" => self.last_background_pid.map(|pid| pid.to_string()),
+            "0" => Some(self.arg0.clone()),
+            "#" => Some(self.positional.len().to_string()),
+            "@" => Some(self.positional.join(" ")),
+            "*" => {
+                let ifs = self.state.vars.get("IFS").unwrap_or(" ");
+                let separator = ifs.chars().next().map(String::from).unwrap_or_default();
+                Some(self.positional.join(&separator))
+            }
+            _ if name.bytes().all(|b| b.is_ascii_digit()) => {
+                let n: usize = name.parse().ok()?;
+                self.positional.get(n.checked_sub(1)?).cloned()
+            }
+            // Like any array, `$PIPESTATUS` is its first element
+            "PIPESTATUS" => self.array("PIPESTATUS").into_iter().next(),
+            _ => match name.strip_suffix(']').and_then(|name| name.split_once('[')) {
+                Some((name, index)) => {
+                    let values = self.array(name);
+                    match index {
+                        "@" | "*" => (!values.is_empty()).then(|| values.join(" ")),
+                        _ => values.get(index.trim().parse::<usize>().ok()?).cloned(),
+                    }
+                }
+                None => self.state.vars.get(name).map(str::to_string),
+            },
+        }
+    }
+
+    /// Elements of the array `name` for `${name[i]}`. `PIPESTATUS` is the
+    /// only array; any other variable is an array of just its value, like
+    /// in bash.
+    pub(crate) fn array(&self, name: &str) -> Vec<String> {
+        match name {
+            "PIPESTATUS" => self.state.pipestatus.iter().map(i32::to_string).collect(),
+            _ => self.get_var(name).into_iter().collect(),
+        }
+    }
+
+    /// Executes the commands of a compound command's body in order and
+    /// returns the status of the last one
+    pub(crate) fn execute_body(&mut self, list: &List, io: &Io) -> i32 {
+        let mut status = 0;
+        for item in &list.items {
+            self.run_pending_traps(io);
+            if self.aborted(io) {
+                break;
+            }
+            status = if item.background {
+                self.execute_background(item, io)
+            } else {
+                self.execute_and_or(&item.and_or, io)
+            };
+            self.state.last_status = status;
+        }
+        status
+    }
+
+    /// Runs pipelines joined by `&&` and `||`, skipping those whose
+    /// condition fails
+    pub(crate) fn execute_and_or(&mut self, and_or: &AndOr, io: &Io) -> i32 {
+        let last = and_or.rest.len();
+        let mut status = self.execute_checked(&and_or.first, last == 0, io);
+        for (i, (op, pipeline)) in and_or.rest.iter().enumerate() {
+            self.run_pending_traps(io);
+            if self.aborted(io) {
+                break;
+            }
+            self.state.last_status = status;
+            let run = match op {
+                AndOrOp::And => status == 0,
+                AndOrOp::Or => status != 0,
+            };
+            if run {
+                status = self.execute_checked(pipeline, i + 1 == last, io);
+            }
+        }
+        status
+    }
+
+    /// Runs a pipeline of an `&&`/`||` list. Only the failure of the last
+    /// pipeline counts as an error; the others, like negated pipelines, are
+    /// tested as conditions.
+    fn execute_checked(&mut self, pipeline: &Pipeline, last: bool, io: &Io) -> i32 {
+        if !last || pipeline.negated {
+            self.condition_depth += 1;
+            let status = self.execute_foreground(pipeline, io);
+            self.condition_depth -= 1;
+            return status;
+        }
+        let status = self.execute_foreground(pipeline, io);
+        if status != 0 {
+            self.command_failed(pipeline, status, io);
+        }
+        status
+    }
+
+    /// Executes the condition of an `if`, `while` or `until`
+    pub(crate) fn execute_condition(&mut self, list: &List, io: &Io) -> i32 {
+        self.condition_depth += 1;
+        let status = self.execute_body(list, io);
+        self.condition_depth -= 1;
+        status
+    }
+
+    /// Whether the remaining commands of a list must be skipped because of
+    /// `^C` or a pending `break`, `continue`, `return` or `exit`
+    fn aborted(&mut self, io: &Io) -> bool {
+        self.check_interrupt(io) || self.loop_control.is_some() || self.unwinding()
+    }
+
+    /// Whether `return` or `exit` is leaving every command in progress
+    fn unwinding(&self) -> bool {
+        self.return_status.is_some() || self.exit_status.is_some()
+    }
+
+    /// Whether `^C` interrupted the command line, either by killing a
+    /// foreground command or by reaching the shell itself
+    fn check_interrupt(&mut self, io: &Io) -> bool {
+        if signals::take_interrupt() {
+            // Only builtins were running, so nothing has ended the line after `^C`
+            if self.interactive {
+                writeln!(io.stderr()).ok();
+            }
+            self.interrupted = true;
+        }
+        self.interrupted
+    }
+
+    /// Reports an error on the standard error of `io`. `NO_COLOR` is taken
+    /// from the shell's environment, so that it can be changed in the shell.
+    pub fn report(&self, io: &Io, error: &NeocashError, command: Option<&str>) {
+        io.report(error, command, self.state.vars.get_exported("NO_COLOR"));
+    }
+
+    /// Forgets a `^C` that arrived for an earlier command line, so that it
+    /// does not abort the next one
+    pub(crate) fn clear_interrupt(&mut self) {
+        signals::take_interrupt();
+        self.interrupted = false;
+    }
+
+    /// Resets state that only belongs to the parent in a forked subshell
+    fn enter_subshell(&mut self) {
+        self.shell_pgid = None;
+        self.launch = None;
+        self.state.jobs = JobTable::default();
+        self.reset_traps();
+    }
+
+    /// Executes one command of a pipeline. With `exec` set the shell process
+    /// is replaced by an external program instead of waiting for it; this is
+    /// used inside forked pipeline stages.
+    pub(crate) fn execute_command(&mut self, command: &Command, io: &Io, exec: bool) -> i32 {
+        match command {
+            Command::Simple(simple) => self.execute_simple(simple, io, exec),
+            Command::Compound(compound, redirects) => match self.apply_redirects(redirects, io) {
+                Ok(io) => self.execute_compound(compound, &io),
+                Err(e) => {
+                    self.report(io, &e, None);
+                    1
+                }
+            },
+            Command::FunctionDef { name, body } => {
+                self.state
+                    .functions
+                    .insert(name.clone(), Rc::new((**body).clone()));
+                0
+            }
+        }
+    }
+
+    /// Calls a function with `args[1..]` as its positional parameters
+    fn call_function(&mut self, body: &Command, args: &[String], io: &Io) -> i32 {
+        let positional = std::mem::replace(&mut self.positional, args[1..].to_vec());
+        // `break` and `continue` cannot reach loops outside the function
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        self.function_depth += 1;
+        self.state.vars.push_scope();
+
+        let status = self.execute_command(body, io, false);
+        let status = self.return_status.take().unwrap_or(status);
+
+        self.state.vars.pop_scope();
+        self.function_depth -= 1;
+        self.loop_depth = loop_depth;
+        self.positional = positional;
+        status
+    }
+
+    /// Runs `f` with `assignments` exported as variables that only last for
+    /// the command
+    fn with_assignments(
+        &mut self,
+        assignments: &[(String, String)],
+        f: impl FnOnce(&mut Self) -> i32,
+    ) -> i32 {
+        let saved: Vec<(String, Option<Variable>)> = assignments
+            .iter()
+            .map(|(name, value)| {
+                let old = self.state.vars.unset(name);
+                self.state.vars.set(name, value.as_str());
+                self.state.vars.export(name);
+                (name.clone(), old)
+            })
+            .collect();
+
+        let status = f(self);
+
+        for (name, old) in saved.into_iter().rev() {
+            self.state.vars.unset(&name);
+            if let Some(old) = old {
+                if let Some(value) = old.value {
+                    self.state.vars.set(&name, value);
+                }
+                if old.exported {
+                    self.state.vars.export(&name);
+                }
+            }
+        }
+        status
+    }
+
+    fn execute_simple(&mut self, command: &SimpleCommand, io: &Io, exec: bool) -> i32 {
+        self.substitution_status = None;
+        let expanded = self.expand_command(command, io);
+        let (assignments, args, io) = match expanded {
+            Ok(expanded) => expanded,
+            Err(e) => {
+                self.report(io, &e, None);
+                return 1;
+            }
+        };
+        let io = &io;
+        if self.state.options.xtrace {
+            self.trace(&assignments, &args, io);
+        }
+
+        if args.is_empty() {
+            // Assignments without a command set shell variables
+            for (name, value) in assignments {
+                self.state.vars.set(&name, value);
+            }
+            return self.substitution_status.unwrap_or(0);
+        }
+
+        // Assignments before a function or builtin only last for the command
+        if let Some(body) = self.state.functions.get(&args[0]).cloned() {
+            return self
+                .with_assignments(&assignments, |shell| shell.call_function(&body, &args, io));
+        }
+
+        if let Some(builtin) = self.builtins.get(&args[0]) {
+            return self.with_assignments(&assignments, |shell| {
+                match builtin.execute(shell, &args, io) {
+                    Ok(code) => code,
+                    Err(e) => {
+                        shell.report(io, &e, Some(&args[0]));
+                        e.status()
+                    }
+                }
+            });
+        }
+
+        match self.run_program(&args, &assignments, io, exec) {
+            Ok(status) => status,
+            Err(error) => {
+                self.report(io, &error, None);
+                error.status()
+            }
+        }
+    }
+
+    /// Runs an external program with `env` added to the exported variables
+    /// and waits for it; under job control it is a foreground job. With
+    /// `exec` set the shell process is replaced instead.
+    pub(crate) fn run_program(
+        &mut self,
+        args: &[String],
+        env: &[(String, String)],
+        io: &Io,
+        exec: bool,
+    ) -> Result<i32, NeocashError> {
+        let launch = self.launch;
+        let result = process::external_command(args, &self.state.cwd, io).and_then(|mut cmd| {
+            cmd.env_clear()
+                .envs(self.state.vars.exported())
+                .envs(env.iter().map(|(name, value)| (name, value)));
+            process::set_process_group(&mut cmd, launch);
+            if exec { Err(cmd.exec()) } else { cmd.spawn() }
+        });
+
+        match result {
+            Ok(child) => {
+                let pid = Pid::from_raw(child.id() as i32);
+                self.joined(pid);
+                let process = Process {
+                    pid,
+                    state: ProcessState::Running,
+                };
+                Ok(self.wait_foreground(vec![process], None, io)[0])
+            }
+            Err(source) => {
+                // The child may have taken the terminal before exec failed
+                self.reclaim_terminal();
+                // Paths are reported as they are, only names are looked up
+                Err(match source.kind() {
+                    std::io::ErrorKind::NotFound if !args[0].contains('/') => {
+                        NeocashError::NotFound(args[0].clone())
+                    }
+                    std::io::ErrorKind::PermissionDenied => {
+                        NeocashError::PermissionDenied(args[0].clone())
+                    }
+                    _ => NeocashError::CannotExecute {
+                        command: args[0].clone(),
+                        source,
+                    },
+                })
+            }
+        }
+    }
+
+    /// Expands the assignments, words and redirections of a simple command
+    #[allow(clippy::type_complexity)]
+    fn expand_command(
+        &mut self,
+        command: &SimpleCommand,
+        io: &Io,
+    ) -> Result<(Vec<(String, String)>, Vec<String>, Io), NeocashError> {
+        let assignments = command
+            .assignments
+            .iter()
+            .map(|a| Ok((a.name.clone(), self.expand_word(&a.value, io)?)))
+            .collect::<Result<_, NeocashError>>()?;
+        let args = self.expand_words(&command.words, io)?;
+        let io = self.apply_redirects(&command.redirects, io)?;
+        Ok((assignments, args, io))
+    }
+}
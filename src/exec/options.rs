@@ -0,0 +1,143 @@
+// exec/options.rs
+
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Long names of the options `set` knows, with their single-letter flags
+const OPTIONS: &[(&str, Option<char>)] = &[
+    ("errexit", Some('e')),
+    ("noclobber", Some('C')),
+    ("noglob", Some('f')),
+    ("nounset", Some('u')),
+    ("pipefail", None),
+    ("xtrace", Some('x')),
+];
+
+/// Options changed with `set`, or turned on in the `[options]` table of
+/// the configuration
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShellOptions {
+    /// Exit when a command fails outside of a condition
+    pub errexit: bool,
+    /// Refuse to overwrite existing files with `>`
+    pub noclobber: bool,
+    /// Disable pathname expansion
+    pub noglob: bool,
+    /// Treat the expansion of an unset variable as an error
+    pub nounset: bool,
+    /// Give a pipeline the status of its last failing stage
+    pub pipefail: bool,
+    /// Print every command to standard error, after `PS4`, before it runs
+    pub xtrace: bool,
+}
+
+impl ShellOptions {
+    /// Whether an option is on, by its long name
+    pub fn get(&self, name: &str) -> bool {
+        match name {
+            "errexit" => self.errexit,
+            "noclobber" => self.noclobber,
+            "noglob" => self.noglob,
+            "nounset" => self.nounset,
+            "pipefail" => self.pipefail,
+            "xtrace" => self.xtrace,
+            _ => false,
+        }
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "errexit" => Some(&mut self.errexit),
+            "noclobber" => Some(&mut self.noclobber),
+            "noglob" => Some(&mut self.noglob),
+            "nounset" => Some(&mut self.nounset),
+            "pipefail" => Some(&mut self.pipefail),
+            "xtrace" => Some(&mut self.xtrace),
+            _ => None,
+        }
+    }
+
+    /// Turns an option on or off by its long name (`set -o name`)
+    pub fn set(&mut self, name: &str, on: bool) -> Result<(), NeocashError> {
+        let option = self
+            .get_mut(name)
+            .ok_or_else(|| NeocashError::invalid(name, "invalid option name"))?;
+        *option = on;
+        Ok(())
+    }
+
+    /// Turns an option on or off by its flag (`set -e`)
+    pub fn set_flag(&mut self, flag: char, on: bool) -> Result<(), NeocashError> {
+        let (name, _) = OPTIONS
+            .iter()
+            .find(|(_, f)| *f == Some(flag))
+            .ok_or_else(|| NeocashError::Usage(format!("-{}: invalid option", flag)))?;
+        self.set(name, on)
+    }
+
+    /// Every option with whether it is on, by long name
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, bool)> {
+        OPTIONS.iter().map(|(name, _)| (*name, self.get(name)))
+    }
+
+    /// Flags of the options that are on, as `$-` reports them
+    pub fn flags(&self) -> String {
+        OPTIONS
+            .iter()
+            .filter(|(name, _)| self.get(name))
+            .filter_map(|(_, flag)| *flag)
+            .collect()
+    }
+}
+
+impl Executor {
+    /// Prints a command about to run for `xtrace`
+    pub(crate) fn trace(&self, assignments: &[(String, String)], args: &[String], io: &Io) {
+        let prefix = self.get_var("PS4").unwrap_or_else(|| "+ ".to_string());
+        let words: Vec<String> = assignments
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, quote(value)))
+            .chain(args.iter().map(|arg| quote(arg)))
+            .collect();
+        writeln!(io.stderr(), "{}{}", prefix, words.join(" ")).ok();
+    }
+}
+
+/// Quotes a word for `xtrace` output if it would not read back as itself
+fn quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%^".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_options_table() {
+        let options: ShellOptions = toml::from_str("errexit = true\npipefail = true").unwrap();
+        assert_eq!(options.flags(), "e");
+        assert!(options.get("pipefail"));
+        assert!(!options.get("nounset"));
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        let error = toml::from_str::<ShellOptions>("errexitt = true").unwrap_err();
+        assert!(error.to_string().contains("unknown field `errexitt`"));
+    }
+
+    #[test]
+    fn quotes_traced_words() {
+        assert_eq!(quote("plain-word"), "plain-word");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("it's here"), r"'it'\''s here'");
+    }
+}
@@ -0,0 +1,243 @@
+// exec/pattern.rs
+
+/// A shell pattern with `*`, `?` and `[...]`. A backslash makes the next
+/// character match literally; quoted text is escaped this way before a
+/// pattern is built from it.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    AnyString,
+    /// `[...]`, or `[!...]` when negated
+    Class {
+        negated: bool,
+        items: Vec<ClassItem>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    /// `[:alpha:]` and friends
+    Named(fn(char) -> bool),
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let token = match chars[i] {
+                '\\' if i + 1 < chars.len() => {
+                    i += 1;
+                    Token::Char(chars[i])
+                }
+                '?' => Token::AnyChar,
+                '*' => Token::AnyString,
+                '[' => match parse_class(&chars, i + 1) {
+                    Some((class, end)) => {
+                        i = end;
+                        class
+                    }
+                    // An unterminated bracket is an ordinary character
+                    None => Token::Char('['),
+                },
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+            i += 1;
+        }
+        Self { tokens }
+    }
+
+    /// Whether the pattern contains no special characters
+    pub fn is_literal(&self) -> bool {
+        self.tokens.iter().all(|t| matches!(t, Token::Char(_)))
+    }
+
+    /// Whether the pattern matches the whole of `text`
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let (mut t, mut p) = (0, 0);
+        // Where to resume after the last `*` if the rest fails to match
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while t < text.len() {
+            match self.tokens.get(p) {
+                Some(Token::AnyString) => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                Some(token) if token.matches(text[t]) => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            match backtrack {
+                Some((star, start)) => {
+                    backtrack = Some((star, start + 1));
+                    p = star + 1;
+                    t = start + 1;
+                }
+                None => return false,
+            }
+        }
+        self.tokens[p..]
+            .iter()
+            .all(|t| matches!(t, Token::AnyString))
+    }
+
+    /// Escapes `text` so that it matches only itself
+    pub fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::AnyChar => true,
+            Token::AnyString => false,
+            Token::Class { negated, items } => {
+                let found = items.iter().any(|item| match item {
+                    ClassItem::Range(low, high) => (*low..=*high).contains(&c),
+                    ClassItem::Named(test) => test(c),
+                });
+                found != *negated
+            }
+        }
+    }
+}
+
+/// Parses a bracket expression starting after the `[`. Returns the class
+/// and the index of the closing `]`.
+fn parse_class(chars: &[char], mut i: usize) -> Option<(Token, usize)> {
+    let negated = matches!(chars.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut items = Vec::new();
+    let first = i;
+    loop {
+        let mut c = *chars.get(i)?;
+        match c {
+            // A `]` right after the opening bracket is literal
+            ']' if i > first => return Some((Token::Class { negated, items }, i)),
+            '[' if chars.get(i + 1) == Some(&':') => {
+                let rest: String = chars[i + 2..].iter().collect();
+                let end = rest.find(":]")?;
+                items.push(ClassItem::Named(named_class(&rest[..end])?));
+                i += 2 + rest[..end].chars().count() + 2;
+                continue;
+            }
+            '\\' => {
+                i += 1;
+                c = *chars.get(i)?;
+            }
+            _ => {}
+        }
+
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&c| c != ']') {
+            let mut high = chars[i + 2];
+            i += 2;
+            if high == '\\' {
+                i += 1;
+                high = *chars.get(i)?;
+            }
+            items.push(ClassItem::Range(c, high));
+        } else {
+            items.push(ClassItem::Range(c, c));
+        }
+        i += 1;
+    }
+}
+
+fn named_class(name: &str) -> Option<fn(char) -> bool> {
+    let test: fn(char) -> bool = match name {
+        "alnum" => |c| c.is_alphanumeric(),
+        "alpha" => |c| c.is_alphabetic(),
+        "blank" => |c| c == ' ' || c == '\t',
+        "cntrl" => |c| c.is_control(),
+        "digit" => |c| c.is_ascii_digit(),
+        "graph" => |c| c.is_ascii_graphic(),
+        "lower" => |c| c.is_lowercase(),
+        "print" => |c| c.is_ascii_graphic() || c == ' ',
+        "punct" => |c| c.is_ascii_punctuation(),
+        "space" => |c| c.is_whitespace(),
+        "upper" => |c| c.is_uppercase(),
+        "xdigit" => |c| c.is_ascii_hexdigit(),
+        _ => return None,
+    };
+    Some(test)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Pattern::new(pattern).matches(text)
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "aXbYbc"));
+        assert!(!matches("a*b", "aXbc"));
+        assert!(matches("?x?", "axb"));
+        assert!(!matches("?", ""));
+        assert!(!matches("abc", "ab"));
+    }
+
+    #[test]
+    fn matches_bracket_expressions() {
+        assert!(matches("[abc]", "b"));
+        assert!(matches("[a-c]x", "cx"));
+        assert!(!matches("[!a-c]", "b"));
+        assert!(matches("[^a-c]", "d"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[a-]", "-"));
+        assert!(matches("[[:digit:][:upper:]]*", "7up"));
+        assert!(matches("[[:space:]]", "\t"));
+        assert!(!matches("[[:alpha:]]", "1"));
+    }
+
+    #[test]
+    fn treats_malformed_brackets_as_text() {
+        assert!(matches("[ab", "[ab"));
+        assert!(matches("a[", "a["));
+        assert!(Pattern::new("[ab").is_literal());
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        for text in ["a*b", "[x]", "what?", "back\\slash"] {
+            let pattern = Pattern::new(&Pattern::escape(text));
+            assert!(pattern.is_literal(), "{}", text);
+            assert!(pattern.matches(text), "{}", text);
+        }
+        assert!(!Pattern::new(&Pattern::escape("a*")).matches("ab"));
+        assert!(matches("\\*[\\]]", "*]"));
+    }
+}
@@ -0,0 +1,161 @@
+// exec/pipeline.rs
+
+use crate::error::NeocashError;
+use crate::exec::process::{ProcessGroup, fork_with};
+use crate::exec::{Executor, Io, Process, ProcessState};
+use crate::syntax::{ListItem, Pipeline};
+use nix::unistd::{Pid, setpgid, tcsetpgrp};
+use std::fs::File;
+
+impl Executor {
+    /// Runs a pipeline as a foreground job of its own
+    pub(crate) fn execute_foreground(&mut self, pipeline: &Pipeline, io: &Io) -> i32 {
+        // Pipelines inside a compound command are jobs of their own
+        let outer = self.launch;
+        self.current_command = pipeline.to_string();
+        self.launch = self.shell_pgid.map(|_| ProcessGroup {
+            pgid: None,
+            foreground: true,
+        });
+        let status = self.execute_pipeline(pipeline, io);
+        self.launch = outer;
+        status
+    }
+
+    /// Starts a list item terminated by `&` in a forked subshell and adds it
+    /// to the job table without waiting for it
+    pub(crate) fn execute_background(&mut self, item: &ListItem, io: &Io) -> i32 {
+        let group = self.shell_pgid.map(|_| ProcessGroup {
+            pgid: None,
+            foreground: false,
+        });
+
+        // Without job control a background job must not compete for the terminal
+        let mut io = io.clone();
+        if group.is_none() {
+            match File::open("/dev/null") {
+                Ok(null) => io.set(0, null),
+                Err(_) => io.close(0),
+            }
+        }
+
+        let and_or = &item.and_or;
+        let result = fork_with(group, || {
+            self.enter_subshell();
+            let status = self.execute_and_or(and_or, &io);
+            self.finish(status, &io)
+        });
+
+        match result {
+            Ok(pid) => {
+                if group.is_some() {
+                    setpgid(pid, pid).ok();
+                }
+                let process = Process {
+                    pid,
+                    state: ProcessState::Running,
+                };
+                let id = self.state.jobs.add(pid, and_or.to_string(), vec![process]);
+                if self.job_control() {
+                    writeln!(io.stderr(), "[{}] {}", id, pid).ok();
+                }
+                self.last_background_pid = Some(pid);
+                0
+            }
+            Err(e) => {
+                self.report(&io, &NeocashError::io("fork", e), None);
+                1
+            }
+        }
+    }
+
+    /// Runs a pipeline and returns the status of its last stage.
+    /// A single command runs in the shell process so that builtins like `cd`
+    /// take effect; every stage of a longer pipeline runs in its own process.
+    pub(crate) fn execute_pipeline(&mut self, pipeline: &Pipeline, io: &Io) -> i32 {
+        let statuses = if let [command] = pipeline.commands.as_slice() {
+            vec![self.execute_command(command, io, false)]
+        } else {
+            self.run_stages(pipeline, io)
+        };
+
+        let last = if self.state.options.pipefail {
+            statuses.iter().rev().find(|&&status| status != 0)
+        } else {
+            statuses.last()
+        };
+        let last = last.copied().unwrap_or(0);
+        self.state.pipestatus = statuses;
+
+        match (pipeline.negated, last) {
+            (false, status) => status,
+            (true, 0) => 1,
+            (true, _) => 0,
+        }
+    }
+
+    fn run_stages(&mut self, pipeline: &Pipeline, io: &Io) -> Vec<i32> {
+        let mut children = Vec::new();
+        let mut input = None;
+        let last = pipeline.commands.len() - 1;
+
+        for (i, command) in pipeline.commands.iter().enumerate() {
+            let mut stage_io = io.clone();
+            if let Some(reader) = input.take() {
+                stage_io.set(0, reader);
+            }
+            if i < last {
+                match std::io::pipe() {
+                    Ok((reader, writer)) => {
+                        stage_io.set(1, writer);
+                        input = Some(reader);
+                    }
+                    Err(e) => {
+                        self.report(io, &NeocashError::io("pipe", e), None);
+                        break;
+                    }
+                }
+            }
+
+            // The parent's copies of the pipe ends are dropped with `stage_io`
+            // so that every reader sees end-of-file once its writer exits.
+            let result = fork_with(self.launch, || {
+                self.enter_subshell();
+                let status = self.execute_command(command, &stage_io, true);
+                self.finish(status, &stage_io)
+            });
+            match result {
+                Ok(pid) => {
+                    self.joined(pid);
+                    children.push(Process {
+                        pid,
+                        state: ProcessState::Running,
+                    });
+                }
+                Err(e) => {
+                    self.report(io, &NeocashError::io("fork", e), None);
+                    break;
+                }
+            }
+        }
+
+        if children.is_empty() {
+            return vec![1];
+        }
+        self.wait_foreground(children, None, io)
+    }
+
+    /// Records that `pid` was started for the current job. The first process
+    /// becomes the group leader; the parent sets the group too, so that it is
+    /// in place no matter whether parent or child runs first.
+    pub(crate) fn joined(&mut self, pid: Pid) {
+        let Some(group) = &mut self.launch else {
+            return;
+        };
+        let pgid = *group.pgid.get_or_insert(pid);
+        setpgid(pid, pgid).ok();
+        if group.foreground {
+            tcsetpgrp(0, pgid).ok();
+        }
+    }
+}
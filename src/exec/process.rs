@@ -0,0 +1,147 @@
+// exec/process.rs
+
+use crate::exec::Io;
+use crate::signals;
+use nix::errno::Errno;
+use nix::fcntl::{FcntlArg, FdFlag, fcntl};
+use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
+use nix::unistd::{ForkResult, Pid, dup2, fork, getpid, setpgid, tcsetpgrp};
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a new process is placed when job control is active
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessGroup {
+    /// Group to join; `None` starts a new group led by the process itself
+    pub pgid: Option<Pid>,
+    /// Whether the group should own the terminal
+    pub foreground: bool,
+}
+
+impl ProcessGroup {
+    /// Moves the calling process into the group. Runs in the child between
+    /// fork and exec, so it only makes async-signal-safe calls. A `^C` or
+    /// `^Z` that arrives meanwhile is delivered once the default dispositions
+    /// are back.
+    fn enter(&self) -> nix::Result<()> {
+        let mask = signals::block_job_signals();
+        let pgid = self.pgid.unwrap_or_else(getpid);
+        let result = setpgid(Pid::from_raw(0), pgid).and_then(|()| {
+            if self.foreground {
+                // Failure only means there is no controlling terminal
+                tcsetpgrp(0, pgid).ok();
+            }
+            signals::restore_default_signals()
+        });
+        signals::restore_mask(mask);
+        result
+    }
+}
+
+/// Finds the executable file a command name without `/` runs, searching the
+/// directories of `path` (a `PATH` value) in order
+pub fn find_in_path(name: &str, path: &str) -> Option<PathBuf> {
+    path.split(':')
+        .map(|dir| Path::new(if dir.is_empty() { "." } else { dir }).join(name))
+        .find(|file| {
+            fs::metadata(file).is_ok_and(|meta| meta.is_file() && meta.mode() & 0o111 != 0)
+        })
+}
+
+/// Builds a `Command` for an external program wired to the descriptors in `io`,
+/// started in `cwd`. A program given by a relative path is looked up there too.
+pub fn external_command(args: &[String], cwd: &Path, io: &Io) -> io::Result<Command> {
+    let program = match args[0].contains('/') {
+        true => cwd.join(&args[0]),
+        false => PathBuf::from(&args[0]),
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(&args[1..])
+        .current_dir(cwd)
+        .stdin(io.stdio(0)?)
+        .stdout(io.stdio(1)?)
+        .stderr(io.stdio(2)?);
+
+    let extra_fds = io.extra_fds();
+    if !extra_fds.is_empty() {
+        // SAFETY: only async-signal-safe `dup2` calls run between fork and exec
+        unsafe {
+            cmd.pre_exec(move || {
+                for &(source, target) in &extra_fds {
+                    if source == target {
+                        fcntl(source, FcntlArg::F_SETFD(FdFlag::empty()))?;
+                    } else {
+                        dup2(source, target)?;
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    Ok(cmd)
+}
+
+/// Places a command in `group` before it is spawned or exec'd
+pub fn set_process_group(cmd: &mut Command, group: Option<ProcessGroup>) {
+    if let Some(group) = group {
+        // SAFETY: `ProcessGroup::enter` only makes async-signal-safe calls
+        unsafe {
+            cmd.pre_exec(move || group.enter().map_err(io::Error::from));
+        }
+    }
+}
+
+/// Runs `f` in a forked copy of the shell and returns the child's pid.
+/// The child exits with the status returned by `f`.
+pub fn fork_with(group: Option<ProcessGroup>, f: impl FnOnce() -> i32) -> io::Result<Pid> {
+    // Anything still buffered would otherwise be written twice
+    io::stdout().flush().ok();
+    io::stderr().flush().ok();
+
+    // Keyboard signals wait until the child has left the shell's handlers
+    let mask = signals::block_job_signals();
+    let result = unsafe { fork() };
+    if !matches!(result, Ok(ForkResult::Child)) {
+        signals::restore_mask(mask);
+    }
+    match result.map_err(io::Error::from)? {
+        ForkResult::Parent { child } => Ok(child),
+        ForkResult::Child => {
+            if let Some(group) = group {
+                group.enter().ok();
+            }
+            signals::restore_mask(mask);
+            let status = f();
+            io::stdout().flush().ok();
+            io::stderr().flush().ok();
+            unsafe { nix::libc::_exit(status) }
+        }
+    }
+}
+
+/// Converts a wait status into a shell exit status.
+/// A child killed by a signal reports `128 + signo`.
+pub fn exit_status(status: WaitStatus) -> Option<i32> {
+    match status {
+        WaitStatus::Exited(_, code) => Some(code),
+        WaitStatus::Signaled(_, signal, _) => Some(128 + signal as i32),
+        _ => None,
+    }
+}
+
+/// Waits for a child to terminate or, with `untraced`, to stop
+pub fn wait_pid(pid: Pid, untraced: bool) -> nix::Result<WaitStatus> {
+    let flags = untraced.then_some(WaitPidFlag::WUNTRACED);
+    loop {
+        match waitpid(pid, flags) {
+            Err(Errno::EINTR) => continue,
+            Ok(WaitStatus::Continued(_)) => continue,
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// exec/redirect.rs
+
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io};
+use crate::syntax::{Redirect, RedirectOp};
+use nix::unistd::{mkstemp, unlink};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, Write};
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::path::Path;
+use std::rc::Rc;
+
+impl Executor {
+    /// Returns a copy of `io` with `redirects` applied from left to right
+    pub(crate) fn apply_redirects(
+        &mut self,
+        redirects: &[Redirect],
+        io: &Io,
+    ) -> Result<Io, NeocashError> {
+        let mut io = io.clone();
+        for redirect in redirects {
+            self.apply_redirect(redirect, &mut io)?;
+        }
+        Ok(io)
+    }
+
+    fn apply_redirect(&mut self, redirect: &Redirect, io: &mut Io) -> Result<(), NeocashError> {
+        let target = self.expand_word(&redirect.target, io)?;
+        let fd = redirect.fd.unwrap_or(match redirect.op {
+            RedirectOp::Input
+            | RedirectOp::ReadWrite
+            | RedirectOp::DupInput
+            | RedirectOp::HereDoc
+            | RedirectOp::HereString => 0,
+            _ => 1,
+        });
+        let open_error = |e: io::Error| NeocashError::io(&target, e);
+        let path = self.state.resolve(&target);
+        let noclobber = self.state.options.noclobber;
+
+        match redirect.op {
+            RedirectOp::Input => io.set(fd, File::open(&path).map_err(open_error)?),
+            RedirectOp::Output => io.set(fd, create(&path, noclobber).map_err(open_error)?),
+            RedirectOp::Clobber => io.set(fd, File::create(&path).map_err(open_error)?),
+            RedirectOp::Append => io.set(fd, open_append(&path).map_err(open_error)?),
+            RedirectOp::ReadWrite => {
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(&path)
+                    .map_err(open_error)?;
+                io.set(fd, file);
+            }
+            RedirectOp::OutputAll => {
+                set_output_all(io, create(&path, noclobber).map_err(open_error)?);
+            }
+            RedirectOp::AppendAll => {
+                set_output_all(io, open_append(&path).map_err(open_error)?);
+            }
+            RedirectOp::DupInput | RedirectOp::DupOutput => {
+                if target == "-" {
+                    io.close(fd);
+                } else if let Ok(source) = target.parse::<i32>() {
+                    let file = io
+                        .resolve(source)
+                        .map_err(|e| NeocashError::io(source.to_string(), e))?;
+                    io.set_shared(fd, file);
+                } else if redirect.op == RedirectOp::DupOutput && redirect.fd.is_none() {
+                    // `>&file` is an alias for `&>file`
+                    set_output_all(io, create(&path, noclobber).map_err(open_error)?);
+                } else {
+                    return Err(format!("{}: ambiguous redirect", target).into());
+                }
+            }
+            RedirectOp::HereDoc => {
+                io.set(fd, here_document(&target).map_err(open_error)?);
+            }
+            RedirectOp::HereString => {
+                let content = format!("{}\n", target);
+                io.set(fd, here_document(&content).map_err(open_error)?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens a file for `>`, which must not replace an existing regular file
+/// under `noclobber`. The file is then created exclusively, so that one
+/// appearing after the check cannot be overwritten either; existing files
+/// that are not regular, like `/dev/null`, are opened as they are.
+fn create(path: &Path, noclobber: bool) -> io::Result<File> {
+    if !noclobber {
+        return File::create(path);
+    }
+    if std::fs::metadata(path).is_ok_and(|meta| !meta.is_file()) {
+        return OpenOptions::new().write(true).open(path);
+    }
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => {
+                io::Error::new(e.kind(), "cannot overwrite existing file")
+            }
+            _ => e,
+        })
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+/// Points both stdout and stderr at `file`
+fn set_output_all(io: &mut Io, file: File) {
+    let file = Rc::new(OwnedFd::from(file));
+    io.set_shared(1, Rc::clone(&file));
+    io.set_shared(2, file);
+}
+
+/// Creates a temporary file that is unlinked right away, so that it is
+/// gone once the last descriptor for it is closed
+pub(crate) fn temp_file(name: &str) -> io::Result<File> {
+    let template = std::env::temp_dir().join(format!("neocash-{}-XXXXXX", name));
+    let (fd, path) = mkstemp(&template)?;
+    // SAFETY: `mkstemp` returns a freshly opened descriptor that we now own
+    let file = unsafe { File::from_raw_fd(fd) };
+    unlink(&path)?;
+    Ok(file)
+}
+
+/// Stores here-document contents in an unlinked temporary file, so that
+/// large bodies cannot block the shell the way a pipe would
+fn here_document(content: &str) -> io::Result<File> {
+    let mut file = temp_file("heredoc")?;
+    file.write_all(content.as_bytes())?;
+    file.rewind()?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ShellConfig;
+    use crate::{EvalResult, Shell};
+
+    /// Runs `source` in a directory of its own, named after the test
+    fn run(name: &str, source: &str) -> EvalResult {
+        let dir =
+            std::env::temp_dir().join(format!("neocash-redirect-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = format!("cd '{}'\n{}", dir.display(), source);
+        Shell::new(ShellConfig::default()).eval(&source).unwrap()
+    }
+
+    #[test]
+    fn writes_and_appends_to_files() {
+        let result = run("files", "echo a > f; echo b >> f; cat f; cat < f >g; cat g");
+        assert_eq!(result.stdout, "a\nb\na\nb\n");
+    }
+
+    #[test]
+    fn applies_redirections_from_left_to_right() {
+        let result = run("order", "ls /nonexistent >/dev/null 2>&1");
+        assert_eq!((result.stdout.as_str(), result.stderr.as_str()), ("", ""));
+
+        let result = run("order", "ls /nonexistent 2>&1 >/dev/null");
+        assert!(result.stdout.contains("/nonexistent"));
+        assert_eq!(result.stderr, "");
+
+        let result = run("order", "ls . /nonexistent &>both; cat both");
+        assert!(result.stdout.contains("both") && result.stdout.contains("/nonexistent"));
+    }
+
+    #[test]
+    fn passes_other_descriptors_to_programs() {
+        let result = run(
+            "fds",
+            "sh -c 'echo three >&3' 3>f; cat f; sh -c 'cat <&4' 4<f",
+        );
+        assert_eq!(result.stdout, "three\nthree\n");
+    }
+
+    #[test]
+    fn refuses_to_clobber_under_noclobber() {
+        let result = run(
+            "noclobber",
+            "rm -f f; set -C; echo a >f; echo b >f; echo $?; cat f",
+        );
+        assert_eq!(result.stdout, "1\na\n");
+        assert!(result.stderr.contains("f: cannot overwrite existing file"));
+
+        let result = run("noclobber", "set -C; echo c >|f; echo d >/dev/null; cat f");
+        assert_eq!(result.stdout, "c\n");
+        assert_eq!(result.stderr, "");
+    }
+
+    #[test]
+    fn feeds_here_documents_and_strings() {
+        let result = run(
+            "heredoc",
+            "x=value\ncat <<EOF\n$x here\nEOF\ncat <<'EOF'\n$x there\nEOF\ncat <<< \"$x string\"",
+        );
+        assert_eq!(result.stdout, "value here\n$x there\nvalue string\n");
+    }
+
+    #[test]
+    fn reports_files_that_cannot_be_opened() {
+        let result = run("missing", "cat < missing-file; echo $?");
+        assert_eq!(result.stdout, "1\n");
+        assert!(
+            result
+                .stderr
+                .contains("missing-file: No such file or directory")
+        );
+    }
+}
@@ -0,0 +1,95 @@
+// exec/script.rs
+
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io};
+use crate::syntax;
+use nix::errno::Errno;
+use nix::unistd::read;
+use std::fs;
+
+impl Executor {
+    /// Runs commands from a script, parsing and executing each complete
+    /// command as soon as its last line has been read. A syntax error stops
+    /// the script. Returns the status of the last command.
+    pub fn execute_source(&mut self, lines: impl Iterator<Item = String>) -> i32 {
+        self.execute_lines(lines, &Io::new())
+    }
+
+    /// Runs a file in the current shell, as `source` does. Non-empty `args`
+    /// replace the positional parameters while it runs.
+    pub fn source_file(
+        &mut self,
+        path: &str,
+        args: &[String],
+        io: &Io,
+    ) -> Result<i32, NeocashError> {
+        let source =
+            fs::read_to_string(self.state.resolve(path)).map_err(|e| NeocashError::io(path, e))?;
+        let positional =
+            (!args.is_empty()).then(|| std::mem::replace(&mut self.positional, args.to_vec()));
+        self.source_depth += 1;
+
+        let status = self.execute_lines(source.lines().map(str::to_string), io);
+        let status = self.return_status.take().unwrap_or(status);
+
+        self.source_depth -= 1;
+        if let Some(positional) = positional {
+            self.positional = positional;
+        }
+        Ok(status)
+    }
+
+    /// Runs commands from `lines` like [`execute_source`](Self::execute_source),
+    /// with the descriptors in `io`
+    pub fn execute_lines(&mut self, lines: impl Iterator<Item = String>, io: &Io) -> i32 {
+        let mut source = String::new();
+        // The error for a command that is still open when the input ends
+        let mut unfinished = None;
+        for line in lines {
+            source.push_str(&line);
+            source.push('\n');
+            match syntax::parse_with_aliases(&source, &self.state.aliases) {
+                Ok(list) => {
+                    unfinished = None;
+                    source.clear();
+                    self.execute_body(&list, io);
+                    if self.aborted(io) {
+                        break;
+                    }
+                }
+                Err(e) if e.is_incomplete() => unfinished = Some(e),
+                Err(e) => {
+                    self.report(io, &NeocashError::Parse(e), None);
+                    self.state.last_status = 2;
+                    return 2;
+                }
+            }
+        }
+
+        if let Some(e) = unfinished {
+            self.report(io, &NeocashError::Parse(e), None);
+            self.state.last_status = 2;
+        }
+        self.state.last_status
+    }
+}
+
+/// Lines of standard input. They are read a byte at a time so that
+/// commands reading from standard input themselves see the rest of it.
+pub fn stdin_lines() -> impl Iterator<Item = String> {
+    std::iter::from_fn(|| {
+        let mut line = Vec::new();
+        let mut byte = [0u8];
+        loop {
+            match read(0, &mut byte) {
+                Err(Errno::EINTR) => continue,
+                Ok(0) | Err(_) if line.is_empty() => return None,
+                Ok(0) => break,
+                Ok(_) if byte[0] == b'\n' => break,
+                Ok(_) => line.push(byte[0]),
+                Err(_) => break,
+            }
+        }
+        Some(String::from_utf8_lossy(&line).into_owned())
+    })
+}
@@ -0,0 +1,128 @@
+// exec/state.rs
+
+use crate::error::NeocashError;
+use crate::exec::{JobTable, ShellOptions, Variables};
+use crate::syntax::Command;
+use nix::errno::Errno;
+use nix::unistd::{AccessFlags, access};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+/// Everything a shell remembers between commands. Each shell has its own,
+/// so several of them can run in one process without sharing the working
+/// directory or variables of the process.
+///
+/// Signals are the exception: their dispositions and the record of which
+/// ones arrived belong to the process. A trap set on a signal in one shell
+/// changes how the whole process handles it, and the signal runs the trap
+/// of whichever shell checks for it first.
+#[derive(Debug)]
+pub struct ShellState {
+    /// Working directory, which relative paths are resolved against and
+    /// which child processes start in
+    pub cwd: PathBuf,
+    pub vars: Variables,
+    /// Functions by name; the body is a compound command
+    pub functions: HashMap<String, Rc<Command>>,
+    /// Aliases, expanded when a command line is parsed
+    pub aliases: HashMap<String, String>,
+    /// Abbreviations, expanded by the line editor as they are typed
+    pub abbreviations: HashMap<String, String>,
+    pub jobs: JobTable,
+    /// Options changed with `set` or on the command line
+    pub options: ShellOptions,
+    pub last_status: i32,
+    /// Exit status of every stage of the last pipeline, the array `PIPESTATUS`
+    pub pipestatus: Vec<i32>,
+    /// Commands run on signals and on pseudo-signals like `EXIT`, by the
+    /// name of a [`TrapCondition`](crate::exec::TrapCondition)
+    pub traps: HashMap<String, String>,
+}
+
+impl ShellState {
+    /// Creates the state of a shell working in `cwd` with the given
+    /// variables. `PWD` is set to `cwd`.
+    pub fn new(cwd: PathBuf, mut vars: Variables) -> Self {
+        vars.set("PWD", cwd.to_string_lossy());
+        vars.export("PWD");
+        Self {
+            cwd,
+            vars,
+            functions: HashMap::new(),
+            aliases: HashMap::new(),
+            abbreviations: HashMap::new(),
+            jobs: JobTable::default(),
+            options: ShellOptions::default(),
+            last_status: 0,
+            pipestatus: Vec::new(),
+            traps: HashMap::new(),
+        }
+    }
+
+    /// Creates the state of a shell started by this process, from its
+    /// environment and working directory. `SHLVL` is incremented.
+    pub fn from_env() -> Self {
+        let mut vars = Variables::from_env();
+        let level = vars
+            .get("SHLVL")
+            .and_then(|level| level.parse::<i64>().ok());
+        vars.set("SHLVL", (level.unwrap_or(0) + 1).max(1).to_string());
+        vars.export("SHLVL");
+
+        // An inherited `PWD` is kept when it names the current directory
+        // through symlinks, like other shells do
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+        let cwd = match vars.get("PWD").map(PathBuf::from) {
+            Some(pwd) if fs::canonicalize(&pwd).is_ok_and(|pwd| pwd == cwd) => pwd,
+            _ => cwd,
+        };
+        Self::new(cwd, vars)
+    }
+
+    /// Resolves a path against the working directory
+    pub fn resolve(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.cwd.join(path)
+    }
+
+    /// Changes the working directory and records it in `PWD` and `OLDPWD`.
+    /// The path is kept as given, through symlinks, and `..` removes its last
+    /// component; the physical path is only used when that names no directory.
+    pub fn chdir(&mut self, path: &str) -> Result<(), NeocashError> {
+        let error = |source| NeocashError::io(path, source);
+        let logical = normalize(&self.resolve(path));
+        let dir = match logical.is_dir() {
+            true => logical,
+            false => fs::canonicalize(self.resolve(path)).map_err(error)?,
+        };
+        if !dir.is_dir() {
+            return Err(error(io::Error::from(Errno::ENOTDIR)));
+        }
+        access(&dir, AccessFlags::X_OK).map_err(|errno| error(errno.into()))?;
+
+        let old = std::mem::replace(&mut self.cwd, dir);
+        self.vars.set("OLDPWD", old.to_string_lossy());
+        self.vars.export("OLDPWD");
+        self.vars.set("PWD", self.cwd.to_string_lossy());
+        self.vars.export("PWD");
+        Ok(())
+    }
+}
+
+/// Removes `.` components and resolves `..` against the component before
+/// it, without looking at the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
@@ -0,0 +1,230 @@
+// exec/trap.rs
+
+use crate::exec::{Executor, Io};
+use crate::signals;
+use crate::syntax::{Command, CompoundCommand, Pipeline};
+use nix::sys::signal::Signal;
+
+/// Something a trap can be set on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapCondition {
+    /// The shell exits
+    Exit,
+    /// A command fails outside of a condition
+    Err,
+    Signal(Signal),
+}
+
+impl TrapCondition {
+    /// Parses `EXIT`, `ERR`, a signal name with or without `SIG`, or a
+    /// number, where 0 stands for `EXIT`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "EXIT" | "0" => Some(Self::Exit),
+            "ERR" => Some(Self::Err),
+            _ => signals::parse_signal(name).map(Self::Signal),
+        }
+    }
+
+    /// Key of the trap in [`ShellState::traps`](crate::exec::ShellState::traps)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Exit => "EXIT",
+            Self::Err => "ERR",
+            Self::Signal(sig) => &sig.as_str()[3..],
+        }
+    }
+
+    /// Name as `trap -p` prints it
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Signal(sig) => sig.as_str(),
+            _ => self.name(),
+        }
+    }
+
+    /// Every condition in the order traps are listed
+    pub fn all() -> impl Iterator<Item = Self> {
+        let signals = signals::TRAPPABLE_SIGNALS.iter().copied().map(Self::Signal);
+        std::iter::once(Self::Exit)
+            .chain(signals)
+            .chain(std::iter::once(Self::Err))
+    }
+}
+
+impl Executor {
+    /// Sets the trap for `condition`. An empty action ignores a signal and
+    /// `None` restores its default behavior.
+    pub fn set_trap(&mut self, condition: TrapCondition, action: Option<&str>) {
+        let name = condition.name().to_string();
+        if let TrapCondition::Signal(sig) = condition {
+            match action {
+                Some("") => signals::ignore(sig),
+                Some(_) => signals::catch(sig),
+                None => signals::reset(sig),
+            }
+        }
+        match action {
+            Some(action) => self.state.traps.insert(name, action.to_string()),
+            None => self.state.traps.remove(&name),
+        };
+    }
+
+    /// Current traps in listing order
+    pub fn traps(&self) -> Vec<(TrapCondition, &str)> {
+        TrapCondition::all()
+            .filter_map(|condition| {
+                let action = self.state.traps.get(condition.name())?;
+                Some((condition, action.as_str()))
+            })
+            .collect()
+    }
+
+    /// Drops the traps a subshell does not inherit. Ignored signals stay
+    /// ignored; caught ones get their default behavior back.
+    pub(crate) fn reset_traps(&mut self) {
+        for sig in signals::TRAPPABLE_SIGNALS {
+            let condition = TrapCondition::Signal(*sig);
+            if self
+                .state
+                .traps
+                .get(condition.name())
+                .is_some_and(|a| !a.is_empty())
+            {
+                signals::reset(*sig);
+            }
+        }
+        self.state
+            .traps
+            .retain(|name, action| action.is_empty() && !matches!(name.as_str(), "EXIT" | "ERR"));
+    }
+
+    /// Runs the traps of the signals that arrived since the last check
+    pub fn run_pending_traps(&mut self, io: &Io) {
+        for sig in signals::take_pending() {
+            let condition = TrapCondition::Signal(sig);
+            if let Some(action) = self.state.traps.get(condition.name()).cloned() {
+                self.run_trap(&action, io);
+            }
+        }
+    }
+
+    /// Runs the `ERR` trap after `pipeline` failed, and exits with
+    /// `errexit`. Compound commands other than subshells reported the
+    /// failure of their own commands already, and functions only report it
+    /// to their caller.
+    pub(crate) fn command_failed(&mut self, pipeline: &Pipeline, status: i32, io: &Io) {
+        if self.condition_depth > 0 || self.unwinding() {
+            return;
+        }
+        let compound = matches!(
+            pipeline.commands.last(),
+            Some(Command::Compound(compound, _)) if !matches!(compound, CompoundCommand::Subshell(_))
+        );
+        if !compound
+            && !self.in_trap
+            && self.function_depth == 0
+            && let Some(action) = self.state.traps.get("ERR").cloned()
+        {
+            self.state.last_status = status;
+            self.run_trap(&action, io);
+        }
+        if self.state.options.errexit {
+            self.exit_status.get_or_insert(status);
+        }
+    }
+
+    /// Runs a trap action without disturbing `$?` or an interrupted command
+    /// line. An `exit` in the action still exits the shell.
+    fn run_trap(&mut self, action: &str, io: &Io) {
+        if action.is_empty() {
+            return;
+        }
+        let status = self.state.last_status;
+        let interrupted = self.check_interrupt(io);
+        self.interrupted = false;
+        let in_trap = std::mem::replace(&mut self.in_trap, true);
+        self.execute_lines(action.lines().map(str::to_string), io);
+        self.in_trap = in_trap;
+        self.interrupted = interrupted;
+        self.state.last_status = status;
+    }
+
+    /// Runs the `EXIT` trap, with the descriptors in `io`, as the shell or a
+    /// subshell exits. Returns the final exit status: the one given to
+    /// `exit`, or else `status`.
+    pub fn finish(&mut self, status: i32, io: &Io) -> i32 {
+        let status = self.exit_status.take().unwrap_or(status);
+        if let Some(action) = self.state.traps.remove("EXIT") {
+            self.state.last_status = status;
+            self.execute_lines(action.lines().map(str::to_string), io);
+        }
+        self.exit_status.take().unwrap_or(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ShellConfig;
+    use crate::{EvalResult, Shell};
+
+    fn run(source: &str) -> EvalResult {
+        Shell::new(ShellConfig::default()).eval(source).unwrap()
+    }
+
+    #[test]
+    fn parses_conditions() {
+        assert_eq!(TrapCondition::parse("exit"), Some(TrapCondition::Exit));
+        assert_eq!(TrapCondition::parse("0"), Some(TrapCondition::Exit));
+        assert_eq!(TrapCondition::parse("ERR"), Some(TrapCondition::Err));
+        for name in ["INT", "sigint", "2"] {
+            let condition = TrapCondition::parse(name);
+            assert_eq!(
+                condition,
+                Some(TrapCondition::Signal(Signal::SIGINT)),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(TrapCondition::parse("bogus"), None);
+
+        let condition = TrapCondition::Signal(Signal::SIGUSR1);
+        assert_eq!(
+            (condition.name(), condition.display_name()),
+            ("USR1", "SIGUSR1")
+        );
+    }
+
+    #[test]
+    fn runs_the_err_trap_outside_of_conditions() {
+        let source = "trap 'echo err $?' ERR\nfalse; echo $?\nif false; then :; fi\nfalse || true\n! true\nf() { false; }\nf";
+        assert_eq!(run(source).stdout, "err 1\n1\nerr 1\n");
+    }
+
+    #[test]
+    fn lists_and_resets_traps() {
+        let result = run("trap \"echo 'quoted'\" EXIT\ntrap ':' USR1 ERR\ntrap\ntrap -p ERR");
+        assert_eq!(
+            result.stdout,
+            "trap -- 'echo '\\''quoted'\\''' EXIT\n\
+             trap -- ':' SIGUSR1\n\
+             trap -- ':' ERR\n\
+             trap -- ':' ERR\n"
+        );
+
+        let result = run("trap ':' USR1 ERR\ntrap - USR1\ntrap ERR\ntrap");
+        assert_eq!(result.stdout, "");
+    }
+
+    #[test]
+    fn reports_unknown_conditions() {
+        let result = run("trap ':' BOGUS EXIT; echo $?; trap -p");
+        assert_eq!(result.stdout, "1\ntrap -- ':' EXIT\n");
+        assert!(
+            result
+                .stderr
+                .contains("BOGUS: invalid signal specification")
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// exec/vars.rs
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct Variable {
+    /// `None` for a name that was exported before it was given a value
+    pub value: Option<String>,
+    pub exported: bool,
+}
+
+/// Shell variables. Exported ones make up the environment of child processes.
+#[derive(Debug, Clone, Default)]
+pub struct Variables {
+    vars: HashMap<String, Variable>,
+    /// For every function call in progress, innermost last, the variables
+    /// it made local together with the values they had before
+    scopes: Vec<Vec<(String, Option<Variable>)>>,
+}
+
+impl Variables {
+    /// Creates the variable table from the process environment
+    pub fn from_env() -> Self {
+        let vars = std::env::vars()
+            .map(|(name, value)| {
+                let var = Variable {
+                    value: Some(value),
+                    exported: true,
+                };
+                (name, var)
+            })
+            .collect();
+        Self {
+            vars,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).and_then(|var| var.value.as_deref())
+    }
+
+    /// The value of a variable if it is exported
+    pub fn get_exported(&self, name: &str) -> Option<&str> {
+        self.vars
+            .get(name)
+            .filter(|var| var.exported)
+            .and_then(|var| var.value.as_deref())
+    }
+
+    /// Sets a variable, keeping its export flag if it already exists
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        self.vars.entry(name.to_string()).or_default().value = Some(value.into());
+    }
+
+    pub fn export(&mut self, name: &str) {
+        self.vars.entry(name.to_string()).or_default().exported = true;
+    }
+
+    /// Removes the export flag but keeps the value (`export -n`)
+    pub fn unexport(&mut self, name: &str) {
+        if let Some(var) = self.vars.get_mut(name) {
+            var.exported = false;
+        }
+    }
+
+    pub fn unset(&mut self, name: &str) -> Option<Variable> {
+        self.vars.remove(name)
+    }
+
+    /// Starts the scope of a function call
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    /// Ends the scope of a function call, restoring the variables it made
+    /// local
+    pub fn pop_scope(&mut self) {
+        let Some(saved) = self.scopes.pop() else {
+            return;
+        };
+        for (name, old) in saved.into_iter().rev() {
+            match old {
+                Some(var) => self.vars.insert(name, var),
+                None => self.vars.remove(&name),
+            };
+        }
+    }
+
+    /// Makes `name` local to the innermost function call, leaving it unset
+    /// until it is assigned. Returns `false` outside of functions.
+    pub fn make_local(&mut self, name: &str) -> bool {
+        let Some(scope) = self.scopes.last_mut() else {
+            return false;
+        };
+        if !scope.iter().any(|(local, _)| local == name) {
+            scope.push((name.to_string(), self.vars.remove(name)));
+        }
+        true
+    }
+
+    /// Variables sorted by name
+    pub fn iter(&self) -> Vec<(&str, &Variable)> {
+        let mut vars: Vec<_> = self.vars.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        vars.sort_by_key(|(name, _)| *name);
+        vars
+    }
+
+    /// The environment passed to child processes
+    pub fn exported(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars
+            .iter()
+            .filter_map(|(name, var)| match (&var.value, var.exported) {
+                (Some(value), true) => Some((name.as_str(), value.as_str())),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_export_flag_across_assignments() {
+        let mut vars = Variables::default();
+        vars.export("A");
+        assert_eq!(vars.get("A"), None);
+        vars.set("A", "1");
+        vars.set("B", "2");
+        assert_eq!(vars.get_exported("A"), Some("1"));
+        assert_eq!(vars.get_exported("B"), None);
+        assert_eq!(vars.exported().collect::<Vec<_>>(), [("A", "1")]);
+
+        vars.unexport("A");
+        assert_eq!(vars.get("A"), Some("1"));
+        assert_eq!(vars.exported().count(), 0);
+    }
+
+    #[test]
+    fn restores_variables_made_local() {
+        let mut vars = Variables::default();
+        vars.set("x", "global");
+        assert!(!vars.make_local("x"));
+
+        vars.push_scope();
+        assert!(vars.make_local("x"));
+        assert!(vars.make_local("new"));
+        assert_eq!(vars.get("x"), None);
+        vars.set("x", "outer");
+        vars.set("new", "value");
+
+        vars.push_scope();
+        vars.make_local("x");
+        vars.set("x", "inner");
+        // Making a name local twice keeps the value from before the first time
+        vars.make_local("x");
+        vars.pop_scope();
+        assert_eq!(vars.get("x"), Some("outer"));
+
+        vars.pop_scope();
+        assert_eq!(vars.get("x"), Some("global"));
+        assert_eq!(vars.get("new"), None);
+    }
+
+    #[test]
+    fn keeps_assignments_to_variables_that_are_not_local() {
+        let mut vars = Variables::default();
+        vars.push_scope();
+        vars.set("y", "set in a function");
+        vars.pop_scope();
+        assert_eq!(vars.get("y"), Some("set in a function"));
+    }
+}
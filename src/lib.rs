@@ -4,5 +4,12 @@ pub mod cli;
 pub mod commands;
 pub mod completion;
 pub mod config;
+pub mod error;
+pub mod exec;
 pub mod prompt;
+pub mod shell;
+pub mod signals;
+pub mod syntax;
 pub mod version;
+
+pub use shell::{EvalResult, Output, Shell};
@@ -1,23 +1,26 @@
 // main.rs
 
 use clap::Parser;
-use ctrlc::set_handler;
 use neocash::cli::Args;
-use neocash::commands;
-use neocash::completion::ShellCompleter;
+use neocash::completion::{AbbreviationHandler, ShellCompleter};
 use neocash::config::ShellConfig;
+use neocash::error::NeocashError;
+use neocash::exec::{self, Executor, Io};
 use neocash::prompt::{get_prompt_context, render_prompt};
+use neocash::syntax;
 use neocash::version;
-use nix::sys::signal::{SigHandler, Signal, signal};
 use rustyline::{
-    Config, Context, Editor, Helper, completion::Completer, error::ReadlineError,
-    highlight::Highlighter, hint::Hinter, validate::Validator,
+    Config, Context, Editor, EventHandler, Helper, KeyCode, KeyEvent, Modifiers,
+    completion::Completer,
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    validate::{ValidationContext, ValidationResult, Validator},
 };
 use semver::Version;
 use std::fs;
-use std::sync::atomic::{AtomicBool, Ordering};
-
-static RUNNING: AtomicBool = AtomicBool::new(true);
+use std::io::{self, IsTerminal};
+use std::path::Path;
 
 struct ShellHelper {
     completer: ShellCompleter,
@@ -41,11 +44,18 @@ impl Highlighter for ShellHelper {}
 impl Hinter for ShellHelper {
     type Hint = String;
 }
-impl Validator for ShellHelper {}
+impl Validator for ShellHelper {
+    /// Keeps reading lines while the input ends inside a quote, a compound
+    /// command or a here-document
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        match syntax::parse(ctx.input()) {
+            Err(e) if e.is_incomplete() => Ok(ValidationResult::Incomplete),
+            _ => Ok(ValidationResult::Valid(None)),
+        }
+    }
+}
 
 fn main() {
-    setup_signal_handlers();
-
     // --- COMMAND LINE ARGUMENTS PARSING ---
     let args = Args::parse(); // Using `clap` for argument parsing
 
@@ -55,8 +65,56 @@ fn main() {
     }
     // --- END OF COMMAND LINE ARGUMENTS PARSING ---
 
-    let config = ShellConfig::load(args.config_path);
-    println!("Config location: {:?}", config.get_active_config_path());
+    let config = ShellConfig::load(args.config_path.clone());
+    let interactive = args.command.is_none() && args.args.is_empty() && io::stdin().is_terminal();
+    // `login` starts shells with a `-` in front of their name
+    let login = args.login
+        || std::env::args_os()
+            .next()
+            .is_some_and(|arg0| arg0.to_string_lossy().starts_with('-'));
+
+    if interactive {
+        println!("Config location: {:?}", config.get_active_config_path());
+    }
+    let history_path = config.get_history_path();
+    let mut executor = Executor::new(config);
+    executor.login = login;
+    executor.interactive = interactive;
+    if let Err(e) = set_options(&mut executor, &args) {
+        executor.report(&Io::new(), &e, None);
+        std::process::exit(2);
+    }
+    if interactive {
+        executor.enable_job_control();
+    }
+
+    let profile_path = ShellConfig::get_profile_path();
+    if login && profile_path.exists() {
+        source_startup_file(&mut executor, &profile_path);
+    }
+
+    // Scripts, `-c` strings and piped input run without the interactive UI
+    if !interactive {
+        let status = match executor.exit_status {
+            Some(status) => status,
+            None => {
+                let status = run_script(&mut executor, args.command, args.args);
+                executor.exit_status.unwrap_or(status)
+            }
+        };
+        std::process::exit(finish(&mut executor, login, status));
+    }
+
+    if !args.norc && executor.exit_status.is_none() {
+        // A missing default script is fine, but not one given explicitly
+        let rc_path = match args.rcfile {
+            Some(path) => Some(path),
+            None => Some(ShellConfig::get_default_rc_path()).filter(|path| path.exists()),
+        };
+        if let Some(path) = rc_path {
+            source_startup_file(&mut executor, &path);
+        }
+    }
 
     let rl_config = Config::builder()
         .completion_type(rustyline::CompletionType::List)
@@ -66,12 +124,12 @@ fn main() {
     let helper = ShellHelper {
         completer: ShellCompleter::new(),
     };
+    let abbreviations = helper.completer.abbreviations();
     rl.set_helper(Some(helper));
-
-    let history_path = config.get_history_path();
-    if let Some(parent) = history_path.parent() {
-        fs::create_dir_all(parent).ok();
-    }
+    rl.bind_sequence(
+        KeyEvent(KeyCode::Char(' '), Modifiers::NONE),
+        EventHandler::Conditional(Box::new(AbbreviationHandler::new(abbreviations.clone()))),
+    );
 
     if !args.no_history { // <-- Check for not no_history flag
         if let Some(parent) = history_path.parent() {
@@ -83,18 +141,20 @@ fn main() {
         }
     }
 
-    if let Err(e) = rl.load_history(&history_path) {
-        eprintln!("No command history: {}", e);
-    }
-
-    let mut last_exit_code = 0;
+    while executor.exit_status.is_none() {
+        executor.run_pending_traps(&Io::new());
+        executor.notify_jobs(&Io::new());
+        if let Some(helper) = rl.helper_mut() {
+            helper.completer.sync(&executor.state, &executor.builtins);
+        }
+        abbreviations.update(&executor.state.abbreviations);
 
-    while RUNNING.load(Ordering::Relaxed) {
-        let context = get_prompt_context(last_exit_code, &config);
-        let prompt = render_prompt(&config, &context);
+        let context = get_prompt_context(&executor.state, &executor.config);
+        let prompt = render_prompt(&executor.config, &context);
 
         match rl.readline(&prompt) {
             Ok(line) => {
+                let line = abbreviations.expand_line(line);
                 let line = line.trim();
                 if line.is_empty() {
                     continue;
@@ -104,17 +164,18 @@ fn main() {
                     rl.add_history_entry(line).ok();
                 }
 
-                if line == "exit" {
-                    break;
-                }
-
-                last_exit_code = execute_command(line, &config);
+                executor.execute_line(line);
             }
 
+            // Ctrl-C at the prompt discards the line, like in other shells
             Err(ReadlineError::Interrupted) => {
-                println!("Type 'exit' to quit");
+                executor.state.last_status = 130;
+            }
+            Err(ReadlineError::Eof) => {
+                if executor.confirm_exit(&Io::new()) {
+                    break;
+                }
             }
-            Err(ReadlineError::Eof) => break,
             Err(err) => {
                 eprintln!("Error: {:?}", err);
                 break;
@@ -122,54 +183,82 @@ fn main() {
         }
     }
 
-    if let Err(e) = rl.save_history(&history_path) {
+    if !args.no_history
+        && let Err(e) = rl.save_history(&history_path)
+    {
         eprintln!("Failed to save history: {}", e);
     }
+
+    let status = executor.exit_status.unwrap_or(executor.state.last_status);
+    std::process::exit(finish(&mut executor, login, status));
 }
 
-fn setup_signal_handlers() {
-    unsafe {
-        signal(Signal::SIGINT, SigHandler::SigIgn).expect("Error ignoring SIGINT");
+/// Runs the logout script of a login shell and then the `EXIT` trap, and
+/// returns the status the shell exits with
+fn finish(executor: &mut Executor, login: bool, status: i32) -> i32 {
+    // The logout script has to run to completion despite the pending `exit`
+    executor.exit_status = None;
+    let logout_path = ShellConfig::get_logout_path();
+    if login && logout_path.exists() {
+        source_startup_file(executor, &logout_path);
     }
+    executor.finish(status, &Io::new())
+}
 
-    set_handler(move || {
-        RUNNING.store(false, Ordering::Relaxed);
-    })
-    .expect("Error setting Ctrl-C handler");
+/// Turns on the shell options given on the command line
+fn set_options(executor: &mut Executor, args: &Args) -> Result<(), NeocashError> {
+    let flags = [
+        (args.errexit, "errexit"),
+        (args.nounset, "nounset"),
+        (args.xtrace, "xtrace"),
+        (args.noglob, "noglob"),
+        (args.noclobber, "noclobber"),
+    ];
+    let names = flags.iter().filter(|(on, _)| *on).map(|(_, name)| *name);
+    for name in names.chain(args.options.iter().map(String::as_str)) {
+        executor.state.options.set(name, true)?;
+    }
+    Ok(())
 }
 
-fn execute_command(cmd: &str, config: &ShellConfig) -> i32 {
-    let expanded_cmd = shellexpand::tilde(cmd).into_owned();
+/// Runs a startup or logout script in the shell itself, reporting a file
+/// that cannot be read
+fn source_startup_file(executor: &mut Executor, path: &Path) {
+    let io = Io::new();
+    if let Err(e) = executor.source_file(&path.to_string_lossy(), &[], &io) {
+        executor.report(&io, &e, None);
+    }
+}
 
-    // Check if the command is a built-in command
-    match commands::execute(&expanded_cmd, config) {
-        Ok(code) => return code,
-        Err(e) if e.contains("No such file or directory") => {
-            eprintln!("{}", e);
-            return 1;
+/// Runs a `-c` string, a script file or the commands piped to standard
+/// input, and returns the exit status for the shell
+fn run_script(executor: &mut Executor, command: Option<String>, args: Vec<String>) -> i32 {
+    let mut args = args.into_iter();
+    if let Some(command) = command {
+        if let Some(arg0) = args.next() {
+            executor.arg0 = arg0;
         }
-        Err(_) => {} // Ignore other errors for now
+        executor.positional = args.collect();
+        return executor.execute_source(command.lines().map(str::to_string));
     }
 
-    // If not a built-in command, execute it as a shell command
-    let output = std::process::Command::new("sh")
-        .arg("-c")
-        .arg(&expanded_cmd)
-        .spawn();
-
-    match output {
-        Ok(mut child) => match child.wait() {
-            Ok(status) => status.code().unwrap_or(1),
-            Err(e) => {
-                eprintln!("Command failed: {}", e);
-                1
-            }
-        },
-        Err(e) => {
-            eprintln!("Failed to execute command: {}", e);
-            1
+    let Some(path) = args.next() else {
+        return executor.execute_source(exec::stdin_lines());
+    };
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(source) => {
+            let error = NeocashError::CannotExecute {
+                command: path,
+                source,
+            };
+            executor.report(&Io::new(), &error, None);
+            return error.status();
         }
-    }
+    };
+    executor.arg0 = path;
+    executor.positional = args.collect();
+    executor.execute_source(source.lines().map(str::to_string))
 }
 
 fn show_version(verbose: bool, check_updates: bool) {
@@ -224,7 +313,7 @@ fn show_version(verbose: bool, check_updates: bool) {
                     println!("\n✓ You're up-to-date!");
                 }
             }
-            Err(e) => println!("\n⚠️ Update check failed: {}", e),
+            Err(e) => println!("\n⚠️ Update check failed: {:#}", e),
         }
     }
 }
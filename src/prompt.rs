@@ -1,11 +1,9 @@
 // prompt.rs
 
 use crate::config::{PathDisplayMode, ShellConfig};
+use crate::exec::ShellState;
 use chrono::Local;
-use std::{
-    env,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 pub struct PromptContext {
     pub time: String,
@@ -16,7 +14,10 @@ pub struct PromptContext {
     pub status_icon: String,
 }
 
-pub fn get_prompt_context(last_error: i32, config: &ShellConfig) -> PromptContext {
+/// Collects what the prompt shows about the shell in `state`
+pub fn get_prompt_context(state: &ShellState, config: &ShellConfig) -> PromptContext {
+    let last_error = state.last_status;
+
     let time = if config.prompt.show_time {
         Local::now().format("%H:%M:%S").to_string()
     } else {
@@ -35,9 +36,7 @@ pub fn get_prompt_context(last_error: i32, config: &ShellConfig) -> PromptContex
         String::new()
     };
 
-    let current_path = env::current_dir()
-        .map(|p| p.display().to_string())
-        .unwrap_or_else(|_| "?".to_string());
+    let current_path = state.cwd.display().to_string();
 
     let path = format_path(&current_path, &config.prompt.path_mode);
 
@@ -0,0 +1,156 @@
+// shell.rs
+
+use crate::config::ShellConfig;
+use crate::error::NeocashError;
+use crate::exec::{Executor, Io, ShellState, temp_file};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::time::{Duration, Instant};
+
+/// Where the output of evaluated commands goes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Output {
+    /// Collected into [`EvalResult::stdout`] and [`EvalResult::stderr`]
+    #[default]
+    Capture,
+    /// Written to the standard output and error of the process
+    Stream,
+}
+
+/// What running a piece of shell code produced
+#[derive(Debug, Clone)]
+pub struct EvalResult {
+    /// Exit status of the last command, or the one given to `exit`
+    pub status: i32,
+    /// Captured standard output; empty when streaming
+    pub stdout: String,
+    /// Captured standard error; empty when streaming
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+impl EvalResult {
+    pub fn success(&self) -> bool {
+        self.status == 0
+    }
+}
+
+/// A shell that Rust code drives, for embedding NeoCASH in other programs
+/// or testing it without a terminal. It is not interactive and has no job
+/// control.
+///
+/// Pipelines, subshells and command substitutions (`$(...)`) `fork()` the
+/// process. Only the forking thread lives on in the child, so a host with
+/// other threads (the default test harness among them) risks a child that
+/// deadlocks on a lock one of them held. Run shells from a single-threaded
+/// process where possible.
+///
+/// Shells in one process share its signal handling; see [`ShellState`].
+pub struct Shell {
+    executor: Executor,
+    output: Output,
+}
+
+impl Shell {
+    /// Creates a shell started from the environment and working directory
+    /// of this process
+    pub fn new(config: ShellConfig) -> Self {
+        Self::with_state(config, ShellState::from_env())
+    }
+
+    /// Creates a shell with its own state, such as a working directory and
+    /// variables chosen by the caller
+    pub fn with_state(config: ShellConfig, state: ShellState) -> Self {
+        Self {
+            executor: Executor::with_state(config, state),
+            output: Output::default(),
+        }
+    }
+
+    /// Sets whether output is captured (the default) or streamed
+    pub fn with_output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Runs `source`, which may span several lines, in this shell. Variables,
+    /// functions and the working directory carry over to later calls. `exit`
+    /// runs the `EXIT` trap into the same result; after it nothing more is
+    /// run and its status is returned.
+    ///
+    /// Failing commands are reported through the result; an error is only
+    /// returned when the output cannot be captured.
+    pub fn eval(&mut self, source: &str) -> Result<EvalResult, NeocashError> {
+        let start = Instant::now();
+        let mut io = Io::new();
+        let capture = match self.output {
+            Output::Capture => {
+                let stdout = temp_file("stdout")?;
+                let stderr = temp_file("stderr")?;
+                io.set(1, stdout.try_clone()?);
+                io.set(2, stderr.try_clone()?);
+                Some((stdout, stderr))
+            }
+            Output::Stream => None,
+        };
+
+        let status = match self.executor.exit_status {
+            Some(status) => status,
+            None => {
+                self.executor.clear_interrupt();
+                let status = self
+                    .executor
+                    .execute_lines(source.lines().map(str::to_string), &io);
+                match self.executor.exit_status {
+                    // The shell exits, so its `EXIT` trap runs now, into this result
+                    Some(_) => {
+                        let status = self.executor.finish(status, &io);
+                        self.executor.exit_status = Some(status);
+                        status
+                    }
+                    None => status,
+                }
+            }
+        };
+        drop(io);
+
+        let (stdout, stderr) = match capture {
+            Some((stdout, stderr)) => (read_back(stdout)?, read_back(stderr)?),
+            None => (String::new(), String::new()),
+        };
+        Ok(EvalResult {
+            status,
+            stdout,
+            stderr,
+            duration: start.elapsed(),
+        })
+    }
+
+    /// The state left by the code run so far
+    pub fn state(&self) -> &ShellState {
+        &self.executor.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut ShellState {
+        &mut self.executor.state
+    }
+
+    /// The executor behind the shell, for access to builtins, traps and
+    /// the like
+    pub fn executor(&mut self) -> &mut Executor {
+        &mut self.executor
+    }
+
+    /// Whether `exit` has been run
+    pub fn has_exited(&self) -> bool {
+        self.executor.exit_status.is_some()
+    }
+}
+
+/// Reads everything written to a capture file
+fn read_back(mut file: File) -> Result<String, NeocashError> {
+    let mut bytes = Vec::new();
+    file.rewind()?;
+    file.read_to_end(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
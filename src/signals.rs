@@ -0,0 +1,162 @@
+// signals.rs
+
+use nix::libc::c_int;
+use nix::sys::signal::{
+    SaFlags, SigAction, SigHandler, SigSet, SigmaskHow, Signal, pthread_sigmask, sigaction, signal,
+};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Set when the shell itself receives `SIGINT`
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the interactive dispositions below are in place
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Trapped signals that arrived and whose trap has not run yet, one bit
+/// per signal number
+static PENDING: AtomicU64 = AtomicU64::new(0);
+
+/// Signals ignored with `trap ''`, which stay ignored in child processes
+static IGNORED: AtomicU64 = AtomicU64::new(0);
+
+/// Signals `trap` accepts
+pub const TRAPPABLE_SIGNALS: &[Signal] = &[
+    Signal::SIGHUP,
+    Signal::SIGINT,
+    Signal::SIGQUIT,
+    Signal::SIGTERM,
+    Signal::SIGUSR1,
+    Signal::SIGUSR2,
+];
+
+/// Signals an interactive shell ignores so that only the foreground job
+/// receives them. Children get the default dispositions back before exec.
+const INTERACTIVE_SIGNALS: &[Signal] = &[
+    Signal::SIGINT,
+    Signal::SIGQUIT,
+    Signal::SIGTSTP,
+    Signal::SIGTTIN,
+    Signal::SIGTTOU,
+];
+
+/// Keyboard signals that are held back while a new process joins its group
+/// and gets its default dispositions back. Arriving in between, they would
+/// otherwise run the shell's handler in the child and be lost.
+const JOB_SIGNALS: &[Signal] = &[Signal::SIGINT, Signal::SIGTSTP];
+
+/// Makes the shell immune to keyboard signals. Ctrl-C at the prompt is
+/// handled by the line editor, and while a job runs in the foreground the
+/// terminal delivers the signals to the job's process group instead.
+/// `SIGINT` is only recorded, so that a loop running in the shell between
+/// two commands can still be interrupted.
+pub fn ignore_interactive_signals() {
+    INTERACTIVE.store(true, Ordering::SeqCst);
+    for &sig in INTERACTIVE_SIGNALS {
+        set_handler(sig, interactive_handler(sig));
+    }
+}
+
+fn interactive_handler(sig: Signal) -> SigHandler {
+    match sig {
+        Signal::SIGINT => SigHandler::Handler(record_interrupt),
+        _ => SigHandler::SigIgn,
+    }
+}
+
+fn set_handler(sig: Signal, handler: SigHandler) {
+    let action = SigAction::new(handler, SaFlags::SA_RESTART, SigSet::empty());
+    unsafe { sigaction(sig, &action) }.ok();
+}
+
+extern "C" fn record_interrupt(_: c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn record_signal(signo: c_int) {
+    PENDING.fetch_or(1 << signo, Ordering::SeqCst);
+}
+
+/// Makes `sig` run its trap instead of its default action
+pub fn catch(sig: Signal) {
+    IGNORED.fetch_and(!(1 << sig as i32), Ordering::SeqCst);
+    set_handler(sig, SigHandler::Handler(record_signal));
+}
+
+/// Ignores `sig` in the shell and in the commands it starts
+pub fn ignore(sig: Signal) {
+    IGNORED.fetch_or(1 << sig as i32, Ordering::SeqCst);
+    set_handler(sig, SigHandler::SigIgn);
+}
+
+/// Gives `sig` back the disposition it has without a trap
+pub fn reset(sig: Signal) {
+    IGNORED.fetch_and(!(1 << sig as i32), Ordering::SeqCst);
+    let handler = if INTERACTIVE.load(Ordering::SeqCst) && INTERACTIVE_SIGNALS.contains(&sig) {
+        interactive_handler(sig)
+    } else {
+        SigHandler::SigDfl
+    };
+    set_handler(sig, handler);
+}
+
+/// Returns the trapped signals that arrived since the last call
+pub fn take_pending() -> Vec<Signal> {
+    let pending = PENDING.swap(0, Ordering::SeqCst);
+    TRAPPABLE_SIGNALS
+        .iter()
+        .copied()
+        .filter(|&sig| pending & (1 << sig as i32) != 0)
+        .collect()
+}
+
+/// Finds a signal `trap` accepts by name, with or without the `SIG`
+/// prefix, or by number
+pub fn parse_signal(name: &str) -> Option<Signal> {
+    let sig = match name.parse::<i32>() {
+        Ok(number) => Signal::try_from(number).ok(),
+        Err(_) => {
+            let name = name.to_ascii_uppercase();
+            let name = name.strip_prefix("SIG").unwrap_or(&name);
+            TRAPPABLE_SIGNALS
+                .iter()
+                .copied()
+                .find(|sig| &sig.as_str()[3..] == name)
+        }
+    };
+    sig.filter(|sig| TRAPPABLE_SIGNALS.contains(sig))
+}
+
+/// Returns whether `SIGINT` arrived since the last call
+pub fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
+
+/// Blocks the keyboard signals a job reacts to and returns the previous
+/// signal mask for [`restore_mask`]. Async-signal-safe.
+pub fn block_job_signals() -> SigSet {
+    let mut set = SigSet::empty();
+    for &sig in JOB_SIGNALS {
+        set.add(sig);
+    }
+    let mut old = SigSet::empty();
+    pthread_sigmask(SigmaskHow::SIG_BLOCK, Some(&set), Some(&mut old)).ok();
+    old
+}
+
+/// Puts back a signal mask saved by [`block_job_signals`], delivering the
+/// signals that arrived meanwhile. Async-signal-safe.
+pub fn restore_mask(mask: SigSet) {
+    pthread_sigmask(SigmaskHow::SIG_SETMASK, Some(&mask), None).ok();
+}
+
+/// Restores the default dispositions in a freshly forked child.
+/// Only async-signal-safe calls are made, so this may run before exec.
+pub fn restore_default_signals() -> nix::Result<()> {
+    let ignored = IGNORED.load(Ordering::SeqCst);
+    for &sig in INTERACTIVE_SIGNALS {
+        if ignored & (1 << sig as i32) == 0 {
+            unsafe { signal(sig, SigHandler::SigDfl) }?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,272 @@
+// syntax/ast.rs
+
+/// A sequence of and-or lists separated by `;`, `&` or newlines.
+/// This is what a whole command line parses into.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct List {
+    pub items: Vec<ListItem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    pub and_or: AndOr,
+    /// `true` when the item was terminated by `&`
+    pub background: bool,
+}
+
+/// Pipelines joined with `&&` and `||`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AndOr {
+    pub first: Pipeline,
+    pub rest: Vec<(AndOrOp, Pipeline)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AndOrOp {
+    And,
+    Or,
+}
+
+/// Commands joined with `|`, optionally negated with a leading `!`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub negated: bool,
+    pub commands: Vec<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    /// A compound command with the redirections that follow it
+    Compound(CompoundCommand, Vec<Redirect>),
+    /// `name() body` or `function name body`, where the body is a compound
+    /// command
+    FunctionDef {
+        name: String,
+        body: Box<Command>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompoundCommand {
+    /// `if list; then list; [elif list; then list;]... [else list;] fi`
+    If {
+        branches: Vec<(List, List)>,
+        else_branch: Option<List>,
+    },
+    /// `while list; do list; done`, or `until` when `until` is set
+    While {
+        condition: List,
+        body: List,
+        until: bool,
+    },
+    /// `for name [in word...]; do list; done`. Without `in` the loop runs
+    /// over the positional parameters.
+    For {
+        name: String,
+        words: Option<Vec<Word>>,
+        body: List,
+    },
+    /// `case word in [(]pattern[|pattern]...) list;; ... esac`
+    Case { word: Word, arms: Vec<CaseArm> },
+    /// `( list )`, run in a forked copy of the shell
+    Subshell(List),
+    /// `{ list; }`, run in the shell itself
+    Group(List),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseArm {
+    pub patterns: Vec<Word>,
+    pub body: List,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimpleCommand {
+    /// `NAME=value` words preceding the command name
+    pub assignments: Vec<Assignment>,
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub name: String,
+    pub value: Word,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    /// Explicit file descriptor written before the operator, e.g. the `2` in `2>file`
+    pub fd: Option<i32>,
+    pub op: RedirectOp,
+    pub target: Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectOp {
+    /// `<`
+    Input,
+    /// `>`
+    Output,
+    /// `>|`
+    Clobber,
+    /// `>>`
+    Append,
+    /// `<>`
+    ReadWrite,
+    /// `<&`
+    DupInput,
+    /// `>&`
+    DupOutput,
+    /// `&>`
+    OutputAll,
+    /// `&>>`
+    AppendAll,
+    /// `<<` and `<<-`; the target is the already-read body
+    HereDoc,
+    /// `<<<`
+    HereString,
+}
+
+/// A single shell word. Quoting is kept so that later expansion stages know
+/// which characters are literal.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Word {
+    pub parts: Vec<WordPart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WordPart {
+    /// Unquoted text
+    Literal(String),
+    /// Text from single quotes or a backslash escape
+    Quoted(String),
+    /// Contents of a double-quoted string
+    DoubleQuoted(Vec<WordPart>),
+    /// `$name` or `${name...}`
+    Param(ParamExpansion),
+    /// `$(...)` or `` `...` ``
+    CommandSubst(List),
+    /// `$((...))`; the expression is expanded like a double-quoted string
+    /// before it is evaluated
+    Arith(Vec<WordPart>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamExpansion {
+    pub name: String,
+    pub op: Option<ParamOp>,
+}
+
+/// Operators inside `${...}`. With `colon` set an empty value is treated
+/// like an unset one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamOp {
+    /// `${#name}`
+    Length,
+    /// `${name:-word}`
+    Default { colon: bool, word: Word },
+    /// `${name:=word}`
+    Assign { colon: bool, word: Word },
+    /// `${name:?word}`
+    Error { colon: bool, word: Word },
+    /// `${name:+word}`
+    Alternative { colon: bool, word: Word },
+    /// `${name#pattern}` and `${name##pattern}`
+    RemovePrefix { longest: bool, pattern: Word },
+    /// `${name%pattern}` and `${name%%pattern}`
+    RemoveSuffix { longest: bool, pattern: Word },
+    /// `${name/pattern/replacement}` and its `//`, `/#` and `/%` forms
+    Replace {
+        mode: ReplaceMode,
+        pattern: Word,
+        replacement: Word,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplaceMode {
+    First,
+    All,
+    Prefix,
+    Suffix,
+}
+
+impl Word {
+    pub fn quoted(text: impl Into<String>) -> Self {
+        Self {
+            parts: vec![WordPart::Quoted(text.into())],
+        }
+    }
+
+    /// Returns `true` if any part of the word is quoted or escaped
+    pub fn is_quoted(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| !matches!(part, WordPart::Literal(_)))
+    }
+
+    /// The word's text with quotes removed and no expansion applied
+    pub fn unquoted_text(&self) -> String {
+        fn push(parts: &[WordPart], text: &mut String) {
+            for part in parts {
+                match part {
+                    WordPart::Literal(s) | WordPart::Quoted(s) => text.push_str(s),
+                    WordPart::DoubleQuoted(inner) => push(inner, text),
+                    WordPart::Param(param) => text.push_str(&param.to_string()),
+                    WordPart::CommandSubst(list) => text.push_str(&format!("$({})", list)),
+                    WordPart::Arith(_) => text.push_str(
+                        &Word {
+                            parts: vec![part.clone()],
+                        }
+                        .to_string(),
+                    ),
+                }
+            }
+        }
+
+        let mut text = String::new();
+        push(&self.parts, &mut text);
+        text
+    }
+
+    /// Splits a `NAME=value` word into an assignment
+    pub fn as_assignment(&self) -> Option<Assignment> {
+        let Some(WordPart::Literal(first)) = self.parts.first() else {
+            return None;
+        };
+        let (name, value) = first.split_once('=')?;
+        if !is_name(name) {
+            return None;
+        }
+
+        let mut parts = Vec::new();
+        if !value.is_empty() {
+            parts.push(WordPart::Literal(value.to_string()));
+        }
+        parts.extend(self.parts[1..].iter().cloned());
+        Some(Assignment {
+            name: name.to_string(),
+            value: Word { parts },
+        })
+    }
+
+    /// Returns the word text if it consists of unquoted literal text only.
+    /// Used to recognise reserved words such as `!`.
+    pub fn as_literal(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [WordPart::Literal(s)] => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `s` is a valid variable name
+pub fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
@@ -0,0 +1,324 @@
+// syntax/display.rs
+
+// Turns syntax trees back into shell source, e.g. for the job table
+
+use crate::syntax::ast::{
+    AndOr, AndOrOp, Command, CompoundCommand, List, ListItem, ParamExpansion, ParamOp, Pipeline,
+    Redirect, RedirectOp, ReplaceMode, SimpleCommand, Word, WordPart,
+};
+use std::fmt::{self, Display, Formatter};
+
+impl Display for List {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", item)?;
+            if !item.background && i + 1 < self.items.len() {
+                write!(f, ";")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for ListItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.and_or)?;
+        if self.background {
+            write!(f, " &")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for AndOr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        for (op, pipeline) in &self.rest {
+            let op = match op {
+                AndOrOp::And => "&&",
+                AndOrOp::Or => "||",
+            };
+            write!(f, " {} {}", op, pipeline)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Pipeline {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "! ")?;
+        }
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 {
+                write!(f, " | ")?;
+            }
+            write!(f, "{}", command)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Simple(simple) => write!(f, "{}", simple),
+            Command::Compound(compound, redirects) => {
+                write!(f, "{}", compound)?;
+                for redirect in redirects {
+                    write!(f, " {}", redirect)?;
+                }
+                Ok(())
+            }
+            Command::FunctionDef { name, body } => write!(f, "{}() {}", name, body),
+        }
+    }
+}
+
+impl Display for CompoundCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CompoundCommand::If {
+                branches,
+                else_branch,
+            } => {
+                for (i, (condition, body)) in branches.iter().enumerate() {
+                    write!(f, "{} ", if i == 0 { "if" } else { "elif" })?;
+                    fmt_body(condition, f)?;
+                    write!(f, "then ")?;
+                    fmt_body(body, f)?;
+                }
+                if let Some(body) = else_branch {
+                    write!(f, "else ")?;
+                    fmt_body(body, f)?;
+                }
+                write!(f, "fi")
+            }
+            CompoundCommand::While {
+                condition,
+                body,
+                until,
+            } => {
+                write!(f, "{} ", if *until { "until" } else { "while" })?;
+                fmt_body(condition, f)?;
+                write!(f, "do ")?;
+                fmt_body(body, f)?;
+                write!(f, "done")
+            }
+            CompoundCommand::For { name, words, body } => {
+                write!(f, "for {}", name)?;
+                if let Some(words) = words {
+                    write!(f, " in")?;
+                    for word in words {
+                        write!(f, " {}", word)?;
+                    }
+                }
+                write!(f, "; do ")?;
+                fmt_body(body, f)?;
+                write!(f, "done")
+            }
+            CompoundCommand::Case { word, arms } => {
+                write!(f, "case {} in ", word)?;
+                for arm in arms {
+                    let patterns: Vec<String> =
+                        arm.patterns.iter().map(ToString::to_string).collect();
+                    write!(f, "{}) ", patterns.join(" | "))?;
+                    if !arm.body.items.is_empty() {
+                        write!(f, "{} ", arm.body)?;
+                    }
+                    write!(f, ";; ")?;
+                }
+                write!(f, "esac")
+            }
+            CompoundCommand::Subshell(body) => write!(f, "({})", body),
+            CompoundCommand::Group(body) => {
+                write!(f, "{{ ")?;
+                fmt_body(body, f)?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Writes a list followed by the separator a reserved word needs after it
+fn fmt_body(list: &List, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", list)?;
+    match list.items.last() {
+        Some(item) if item.background => write!(f, " "),
+        _ => write!(f, "; "),
+    }
+}
+
+impl Display for SimpleCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let assignments = self
+            .assignments
+            .iter()
+            .map(|a| format!("{}={}", a.name, a.value));
+        let words = self.words.iter().map(ToString::to_string);
+        let redirects = self.redirects.iter().map(ToString::to_string);
+        let parts: Vec<String> = assignments.chain(words).chain(redirects).collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl Display for Redirect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(fd) = self.fd {
+            write!(f, "{}", fd)?;
+        }
+        let op = match self.op {
+            RedirectOp::Input => "<",
+            RedirectOp::Output => ">",
+            RedirectOp::Clobber => ">|",
+            RedirectOp::Append => ">>",
+            RedirectOp::ReadWrite => "<>",
+            RedirectOp::DupInput => "<&",
+            RedirectOp::DupOutput => ">&",
+            RedirectOp::OutputAll => "&>",
+            RedirectOp::AppendAll => "&>>",
+            // A here-document body always ends with a newline, which is
+            // exactly what a here-string adds back
+            RedirectOp::HereDoc => {
+                let mut body = self.target.clone();
+                trim_trailing_newline(&mut body.parts);
+                return write!(f, "<<< {}", body);
+            }
+            RedirectOp::HereString => "<<< ",
+        };
+        write!(f, "{}{}", op, self.target)
+    }
+}
+
+fn trim_trailing_newline(parts: &mut [WordPart]) {
+    match parts.last_mut() {
+        Some(WordPart::Literal(text) | WordPart::Quoted(text)) if text.ends_with('\n') => {
+            text.pop();
+        }
+        Some(WordPart::DoubleQuoted(inner)) => trim_trailing_newline(inner),
+        _ => {}
+    }
+}
+
+impl Display for Word {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                WordPart::Literal(text) => write!(f, "{}", text)?,
+                WordPart::Quoted(text) => write!(f, "'{}'", text.replace('\'', r"'\''"))?,
+                WordPart::DoubleQuoted(inner) => {
+                    write!(f, "\"")?;
+                    fmt_double_quoted(inner, f)?;
+                    write!(f, "\"")?;
+                }
+                WordPart::Param(param) => write!(f, "{}", param)?,
+                WordPart::CommandSubst(list) => write!(f, "$({})", list)?,
+                WordPart::Arith(expr) => fmt_arith(expr, f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fmt_double_quoted(parts: &[WordPart], f: &mut Formatter<'_>) -> fmt::Result {
+    for part in parts {
+        match part {
+            WordPart::Literal(text) | WordPart::Quoted(text) => {
+                for c in text.chars() {
+                    if matches!(c, '"' | '\\' | '$' | '`') {
+                        write!(f, "\\")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+            }
+            WordPart::DoubleQuoted(inner) => fmt_double_quoted(inner, f)?,
+            WordPart::Param(param) => write!(f, "{}", param)?,
+            WordPart::CommandSubst(list) => write!(f, "$({})", list)?,
+            WordPart::Arith(expr) => fmt_arith(expr, f)?,
+        }
+    }
+    Ok(())
+}
+
+fn fmt_arith(expr: &[WordPart], f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "$((")?;
+    for part in expr {
+        match part {
+            WordPart::Literal(text) => write!(f, "{}", text)?,
+            part => fmt_double_quoted(std::slice::from_ref(part), f)?,
+        }
+    }
+    write!(f, "))")
+}
+
+impl Display for ParamExpansion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.op {
+            None => write!(f, "${{{}}}", self.name),
+            Some(ParamOp::Length) => write!(f, "${{#{}}}", self.name),
+            Some(op) => write!(f, "${{{}{}}}", self.name, op),
+        }
+    }
+}
+
+impl Display for ParamOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let colon = |colon: bool| if colon { ":" } else { "" };
+        match self {
+            ParamOp::Length => Ok(()),
+            ParamOp::Default { colon: c, word } => write!(f, "{}-{}", colon(*c), word),
+            ParamOp::Assign { colon: c, word } => write!(f, "{}={}", colon(*c), word),
+            ParamOp::Error { colon: c, word } => write!(f, "{}?{}", colon(*c), word),
+            ParamOp::Alternative { colon: c, word } => write!(f, "{}+{}", colon(*c), word),
+            ParamOp::RemovePrefix { longest, pattern } => {
+                write!(f, "{}{}", if *longest { "##" } else { "#" }, pattern)
+            }
+            ParamOp::RemoveSuffix { longest, pattern } => {
+                write!(f, "{}{}", if *longest { "%%" } else { "%" }, pattern)
+            }
+            ParamOp::Replace {
+                mode,
+                pattern,
+                replacement,
+            } => {
+                let op = match mode {
+                    ReplaceMode::First => "/",
+                    ReplaceMode::All => "//",
+                    ReplaceMode::Prefix => "/#",
+                    ReplaceMode::Suffix => "/%",
+                };
+                write!(f, "{}{}/{}", op, pattern, replacement)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::parse;
+
+    #[test]
+    fn prints_source_back() {
+        for input in [
+            "a | b && ! c || d &",
+            "x=1 echo 'a b' \"$x ${y:-z}\" 2>&1 >out",
+            "if a; then b; else c; fi",
+            "for i in 1 2; do echo $(( i + 1 )); done",
+            "case $x in a | b) c;; esac",
+            "f() { echo $(date); }",
+        ] {
+            let list = parse(input).unwrap();
+            assert_eq!(parse(&list.to_string()).unwrap(), list, "{}", list);
+        }
+    }
+
+    #[test]
+    fn keeps_simple_commands_as_written() {
+        for input in ["echo 'a b' c", "a | b && c &", "sleep 10 >/dev/null"] {
+            assert_eq!(parse(input).unwrap().to_string(), input);
+        }
+    }
+}
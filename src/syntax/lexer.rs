@@ -0,0 +1,838 @@
+// syntax/lexer.rs
+
+use crate::syntax::ast::{List, ParamExpansion, ParamOp, ReplaceMode, Word, WordPart, is_name};
+use crate::syntax::parser::Parser;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Word(Word),
+    /// A file descriptor number directly followed by a redirection operator
+    IoNumber(i32),
+    Op(Operator),
+    Newline,
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    AndIf,
+    OrIf,
+    Pipe,
+    Amp,
+    Semi,
+    DSemi,
+    LParen,
+    RParen,
+    Less,
+    Great,
+    DGreat,
+    Clobber,
+    LessGreat,
+    LessAnd,
+    GreatAnd,
+    AndGreat,
+    AndDGreat,
+    DLess,
+    DLessDash,
+    TLess,
+}
+
+/// Operators sorted so that longer ones are tried first
+const OPERATORS: &[(&str, Operator)] = &[
+    ("&>>", Operator::AndDGreat),
+    ("<<-", Operator::DLessDash),
+    ("<<<", Operator::TLess),
+    ("&&", Operator::AndIf),
+    ("||", Operator::OrIf),
+    (";;", Operator::DSemi),
+    (">>", Operator::DGreat),
+    (">|", Operator::Clobber),
+    ("<>", Operator::LessGreat),
+    ("<&", Operator::LessAnd),
+    (">&", Operator::GreatAnd),
+    ("&>", Operator::AndGreat),
+    ("<<", Operator::DLess),
+    ("|", Operator::Pipe),
+    ("&", Operator::Amp),
+    (";", Operator::Semi),
+    ("(", Operator::LParen),
+    (")", Operator::RParen),
+    ("<", Operator::Less),
+    (">", Operator::Great),
+];
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        OPERATORS
+            .iter()
+            .find(|(_, op)| op == self)
+            .map(|(s, _)| *s)
+            .unwrap_or("?")
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::IoNumber(n) => write!(f, "{}", n),
+            Token::Op(op) => write!(f, "{}", op.as_str()),
+            Token::Newline => write!(f, "newline"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input ended while a quote or construct was still open
+    Incomplete(String),
+    /// A token appeared where the grammar does not allow it
+    Unexpected(String),
+    /// A malformed `${...}` expansion
+    BadSubstitution(String),
+}
+
+impl ParseError {
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, ParseError::Incomplete(_))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Incomplete(what) => {
+                write!(f, "syntax error: unexpected end of input ({})", what)
+            }
+            ParseError::Unexpected(token) => {
+                write!(f, "syntax error near unexpected token `{}'", token)
+            }
+            ParseError::BadSubstitution(text) => write!(f, "{}: bad substitution", text),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Single-character parameters such as `$?` and `$$`
+fn is_special_param(c: char) -> bool {
+    matches!(c, '?' | '$' | '!' | '#' | '@' | '*' | '-')
+}
+
+fn is_metachar(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t' | '\n' | '|' | '&' | ';' | '(' | ')' | '<' | '>'
+    )
+}
+
+pub struct Lexer<'a> {
+    chars: &'a [char],
+    pos: usize,
+    /// Where lexing resumes after the next newline when here-document
+    /// bodies have been read ahead
+    heredoc_end: Option<usize>,
+    /// Aliases the parser expands in command position
+    aliases: Option<&'a HashMap<String, String>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(chars: &'a [char]) -> Self {
+        Self {
+            chars,
+            pos: 0,
+            heredoc_end: None,
+            aliases: None,
+        }
+    }
+
+    pub fn with_aliases(mut self, aliases: &'a HashMap<String, String>) -> Self {
+        self.aliases = Some(aliases);
+        self
+    }
+
+    pub fn aliases(&self) -> Option<&'a HashMap<String, String>> {
+        self.aliases
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_char_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_char_at(i) == Some(c))
+    }
+
+    /// Skips blanks, line continuations and comments
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek_char() {
+            match c {
+                ' ' | '\t' => self.pos += 1,
+                '\\' if self.peek_char_at(1) == Some('\n') => self.pos += 2,
+                '#' => {
+                    while self.peek_char().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.skip_blanks();
+
+        let Some(c) = self.peek_char() else {
+            return Ok(Token::Eof);
+        };
+
+        if c == '\n' {
+            self.pos = self.heredoc_end.take().unwrap_or(self.pos + 1);
+            return Ok(Token::Newline);
+        }
+
+        for (text, op) in OPERATORS {
+            if self.starts_with(text) {
+                self.pos += text.len();
+                return Ok(Token::Op(*op));
+            }
+        }
+
+        if let Some(n) = self.try_io_number() {
+            return Ok(Token::IoNumber(n));
+        }
+
+        self.read_word().map(Token::Word)
+    }
+
+    /// Recognises a run of digits immediately followed by `<` or `>`
+    fn try_io_number(&mut self) -> Option<i32> {
+        let digits = self.chars[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits == 0 || !matches!(self.peek_char_at(digits), Some('<' | '>')) {
+            return None;
+        }
+
+        let text: String = self.chars[self.pos..self.pos + digits].iter().collect();
+        let n = text.parse().ok()?;
+        self.pos += digits;
+        Some(n)
+    }
+
+    fn read_word(&mut self) -> Result<Word, ParseError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+
+        while let Some(c) = self.peek_char() {
+            if is_metachar(c) {
+                break;
+            }
+            self.pos += 1;
+
+            match c {
+                '\\' => match self.peek_char() {
+                    None => return Err(ParseError::Incomplete("trailing backslash".into())),
+                    Some('\n') => self.pos += 1,
+                    Some(escaped) => {
+                        self.pos += 1;
+                        flush_literal(&mut literal, &mut parts);
+                        parts.push(WordPart::Quoted(escaped.to_string()));
+                    }
+                },
+                '\'' => {
+                    flush_literal(&mut literal, &mut parts);
+                    parts.push(WordPart::Quoted(self.read_single_quoted()?));
+                }
+                '"' => {
+                    flush_literal(&mut literal, &mut parts);
+                    parts.push(WordPart::DoubleQuoted(self.read_double_quoted()?));
+                }
+                '`' => {
+                    flush_literal(&mut literal, &mut parts);
+                    parts.push(self.read_backquoted(false)?);
+                }
+                '$' => match self.read_dollar(false)? {
+                    Some(part) => {
+                        flush_literal(&mut literal, &mut parts);
+                        parts.push(part);
+                    }
+                    None => literal.push('$'),
+                },
+                _ => literal.push(c),
+            }
+        }
+
+        flush_literal(&mut literal, &mut parts);
+        Ok(Word { parts })
+    }
+
+    /// Reads up to the closing `'`; nothing is special inside single quotes
+    fn read_single_quoted(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+        loop {
+            match self.peek_char() {
+                None => return Err(ParseError::Incomplete("unterminated single quote".into())),
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    /// Reads up to the closing `"`
+    fn read_double_quoted(&mut self) -> Result<Vec<WordPart>, ParseError> {
+        self.read_quoted_parts(Some('"'))
+    }
+
+    /// Reads double-quote style text up to `close`, or to the end of input
+    /// when `close` is `None` (here-document bodies). A backslash only escapes
+    /// `$`, `` ` ``, `\`, newline and the closing character; before any other
+    /// character it is kept literally.
+    fn read_quoted_parts(&mut self, close: Option<char>) -> Result<Vec<WordPart>, ParseError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        loop {
+            let Some(c) = self.peek_char() else {
+                if close.is_some() {
+                    return Err(ParseError::Incomplete("unterminated double quote".into()));
+                }
+                break;
+            };
+            self.pos += 1;
+
+            match c {
+                _ if Some(c) == close => break,
+                '\\' => match self.peek_char() {
+                    Some('\n') => self.pos += 1,
+                    Some(escaped)
+                        if matches!(escaped, '$' | '`' | '\\') || Some(escaped) == close =>
+                    {
+                        self.pos += 1;
+                        literal.push(escaped);
+                    }
+                    _ => literal.push('\\'),
+                },
+                '`' => {
+                    flush_literal(&mut literal, &mut parts);
+                    parts.push(self.read_backquoted(true)?);
+                }
+                '$' => match self.read_dollar(true)? {
+                    Some(part) => {
+                        flush_literal(&mut literal, &mut parts);
+                        parts.push(part);
+                    }
+                    None => literal.push('$'),
+                },
+                _ => literal.push(c),
+            }
+        }
+
+        flush_literal(&mut literal, &mut parts);
+        Ok(parts)
+    }
+
+    /// Reads what follows a `$`. Returns `None` when the `$` does not start
+    /// an expansion and is meant literally. `quoted` tells whether the `$`
+    /// appears inside double quotes.
+    fn read_dollar(&mut self, quoted: bool) -> Result<Option<WordPart>, ParseError> {
+        let name = match self.peek_char() {
+            Some('{') => {
+                self.pos += 1;
+                return self.read_braced_param(quoted).map(Some);
+            }
+            Some('(') if self.peek_char_at(1) == Some('(') => {
+                if let Some(part) = self.read_arithmetic()? {
+                    return Ok(Some(part));
+                }
+                self.pos += 1;
+                return self.read_command_substitution().map(Some);
+            }
+            Some('(') => {
+                self.pos += 1;
+                return self.read_command_substitution().map(Some);
+            }
+            Some(c) if is_special_param(c) || c.is_ascii_digit() => {
+                self.pos += 1;
+                c.to_string()
+            }
+            Some(c) if c == '_' || c.is_ascii_alphabetic() => self.read_name(),
+            _ => return Ok(None),
+        };
+        Ok(Some(WordPart::Param(ParamExpansion { name, op: None })))
+    }
+
+    /// Reads `$(...)` after the opening parenthesis. The commands are parsed
+    /// in place so that a `)` inside quotes or a nested substitution does not
+    /// end it early.
+    fn read_command_substitution(&mut self) -> Result<WordPart, ParseError> {
+        let lexer = Lexer {
+            chars: self.chars,
+            pos: self.pos,
+            heredoc_end: None,
+            aliases: self.aliases,
+        };
+        let mut parser = Parser::new(lexer);
+        let list = parser.parse_substitution()?;
+
+        let lexer = parser.into_lexer();
+        self.pos = lexer.pos;
+        if lexer.heredoc_end.is_some() {
+            self.heredoc_end = lexer.heredoc_end;
+        }
+        Ok(WordPart::CommandSubst(list))
+    }
+
+    /// Reads `$((...))` starting at the first parenthesis. Returns `None`
+    /// without consuming anything if the parentheses do not close with `))`,
+    /// as in `$( (cd dir; ls) )`.
+    fn read_arithmetic(&mut self) -> Result<Option<WordPart>, ParseError> {
+        let start = self.pos + 2;
+        let mut depth = 0;
+        let mut end = start;
+        loop {
+            match self.chars.get(end) {
+                None => return Err(ParseError::Incomplete("arithmetic expansion".into())),
+                Some('(') => depth += 1,
+                Some(')') if depth > 0 => depth -= 1,
+                Some(')') if self.chars.get(end + 1) == Some(&')') => break,
+                Some(')') => return Ok(None),
+                _ => {}
+            }
+            end += 1;
+        }
+
+        let expr = &self.chars[start..end];
+        let parts = Lexer::new(expr).read_quoted_parts(None)?;
+        self.pos = end + 2;
+        Ok(Some(WordPart::Arith(parts)))
+    }
+
+    /// Reads a backquoted command substitution after the opening backquote.
+    /// A backslash only escapes `$`, `` ` `` and `\` (and `"` inside double
+    /// quotes); the text in between is then parsed as a separate program.
+    fn read_backquoted(&mut self, quoted: bool) -> Result<WordPart, ParseError> {
+        let mut source = String::new();
+        loop {
+            let Some(c) = self.peek_char() else {
+                return Err(ParseError::Incomplete("unterminated backquote".into()));
+            };
+            self.pos += 1;
+
+            match c {
+                '`' => break,
+                '\\' => match self.peek_char() {
+                    Some(escaped)
+                        if matches!(escaped, '$' | '`' | '\\') || (quoted && escaped == '"') =>
+                    {
+                        self.pos += 1;
+                        source.push(escaped);
+                    }
+                    _ => source.push('\\'),
+                },
+                _ => source.push(c),
+            }
+        }
+
+        let chars: Vec<char> = source.chars().collect();
+        let mut lexer = Lexer::new(&chars);
+        lexer.aliases = self.aliases;
+        let list: List = Parser::new(lexer).parse_program()?;
+        Ok(WordPart::CommandSubst(list))
+    }
+
+    fn read_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self
+            .peek_char()
+            .filter(|&c| c == '_' || c.is_ascii_alphanumeric())
+        {
+            self.pos += 1;
+            name.push(c);
+        }
+        name
+    }
+
+    /// Reads the parameter name at the start of `${...}`: a special
+    /// parameter, a positional parameter of any length or a variable name
+    fn read_param_name(&mut self) -> String {
+        match self.peek_char() {
+            Some(c) if is_special_param(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut digits = String::new();
+                while let Some(d) = self.peek_char().filter(char::is_ascii_digit) {
+                    self.pos += 1;
+                    digits.push(d);
+                }
+                digits
+            }
+            _ => self.read_name(),
+        }
+    }
+
+    /// Reads `${...}` after the opening brace
+    fn read_braced_param(&mut self, quoted: bool) -> Result<WordPart, ParseError> {
+        let start = self.pos - 2;
+
+        // `${#name}` is the length of a value, but `${#}` is the parameter `#`
+        let length = self.peek_char() == Some('#')
+            && !matches!(self.chars.get(self.pos + 1), Some('}') | None);
+        if length {
+            self.pos += 1;
+        }
+
+        let mut name = self.read_param_name();
+        if name.is_empty() {
+            return Err(self.bad_substitution(start));
+        }
+        // A subscript stays part of the name, as in `PIPESTATUS[1]`
+        if is_name(&name) && self.peek_char() == Some('[') {
+            let end = self.chars[self.pos..]
+                .iter()
+                .position(|&c| c == ']' || c == '}');
+            match end.map(|end| self.pos + end) {
+                Some(end) if self.chars[end] == ']' && end > self.pos + 1 => {
+                    name.extend(&self.chars[self.pos..=end]);
+                    self.pos = end + 1;
+                }
+                _ => return Err(self.bad_substitution(start)),
+            }
+        }
+
+        let colon = !length && self.peek_char() == Some(':');
+        if colon {
+            self.pos += 1;
+        }
+
+        let op = match self.peek_char() {
+            None => {
+                return Err(ParseError::Incomplete(
+                    "unterminated parameter expansion".into(),
+                ));
+            }
+            Some('}') if !colon => {
+                self.pos += 1;
+                return Ok(WordPart::Param(ParamExpansion {
+                    name,
+                    op: length.then_some(ParamOp::Length),
+                }));
+            }
+            Some(c @ ('-' | '=' | '?' | '+')) if !length => {
+                self.pos += 1;
+                let (word, _) = self.read_param_word(quoted, false)?;
+                match c {
+                    '-' => ParamOp::Default { colon, word },
+                    '=' => ParamOp::Assign { colon, word },
+                    '?' => ParamOp::Error { colon, word },
+                    _ => ParamOp::Alternative { colon, word },
+                }
+            }
+            Some(c @ ('#' | '%')) if !length && !colon => {
+                self.pos += 1;
+                let longest = self.peek_char() == Some(c);
+                if longest {
+                    self.pos += 1;
+                }
+                let (pattern, _) = self.read_param_word(quoted, false)?;
+                if c == '#' {
+                    ParamOp::RemovePrefix { longest, pattern }
+                } else {
+                    ParamOp::RemoveSuffix { longest, pattern }
+                }
+            }
+            Some('/') if !length && !colon => {
+                self.pos += 1;
+                let mode = match self.peek_char() {
+                    Some('/') => ReplaceMode::All,
+                    Some('#') => ReplaceMode::Prefix,
+                    Some('%') => ReplaceMode::Suffix,
+                    _ => ReplaceMode::First,
+                };
+                if mode != ReplaceMode::First {
+                    self.pos += 1;
+                }
+                let (pattern, end) = self.read_param_word(quoted, true)?;
+                let replacement = match end {
+                    '/' => self.read_param_word(quoted, false)?.0,
+                    _ => Word::default(),
+                };
+                ParamOp::Replace {
+                    mode,
+                    pattern,
+                    replacement,
+                }
+            }
+            _ => return Err(self.bad_substitution(start)),
+        };
+
+        Ok(WordPart::Param(ParamExpansion { name, op: Some(op) }))
+    }
+
+    /// Reads the word operand of a `${...}` operator up to the closing brace,
+    /// or up to a `/` with `slash` set. Returns the word and the character
+    /// that ended it. Inside double quotes single quotes are not special.
+    fn read_param_word(&mut self, quoted: bool, slash: bool) -> Result<(Word, char), ParseError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        loop {
+            let Some(c) = self.peek_char() else {
+                return Err(ParseError::Incomplete(
+                    "unterminated parameter expansion".into(),
+                ));
+            };
+            self.pos += 1;
+
+            match c {
+                '}' => break,
+                '/' if slash => break,
+                '\\' => match self.peek_char() {
+                    None => return Err(ParseError::Incomplete("trailing backslash".into())),
+                    Some('\n') => self.pos += 1,
+                    Some(escaped) if quoted && !matches!(escaped, '$' | '`' | '\\' | '"' | '}') => {
+                        literal.push('\\');
+                    }
+                    Some(escaped) => {
+                        self.pos += 1;
+                        flush_literal(&mut literal, &mut parts);
+                        parts.push(WordPart::Quoted(escaped.to_string()));
+                    }
+                },
+                '\'' if !quoted => {
+                    flush_literal(&mut literal, &mut parts);
+                    parts.push(WordPart::Quoted(self.read_single_quoted()?));
+                }
+                '"' => {
+                    flush_literal(&mut literal, &mut parts);
+                    parts.push(WordPart::DoubleQuoted(self.read_double_quoted()?));
+                }
+                '`' => {
+                    flush_literal(&mut literal, &mut parts);
+                    parts.push(self.read_backquoted(quoted)?);
+                }
+                '$' => match self.read_dollar(quoted)? {
+                    Some(part) => {
+                        flush_literal(&mut literal, &mut parts);
+                        parts.push(part);
+                    }
+                    None => literal.push('$'),
+                },
+                _ => literal.push(c),
+            }
+        }
+
+        flush_literal(&mut literal, &mut parts);
+        Ok((Word { parts }, self.chars[self.pos - 1]))
+    }
+
+    /// Error for a malformed `${...}` starting at `start`, quoting the
+    /// expansion up to its closing brace
+    fn bad_substitution(&self, start: usize) -> ParseError {
+        let mut text: String = self.chars[start..]
+            .iter()
+            .take_while(|&&c| c != '}' && c != '\n')
+            .collect();
+        text.push('}');
+        ParseError::BadSubstitution(text)
+    }
+
+    /// Reads the body of a here-document. The body starts on the line after
+    /// the current one (or after the previous here-document on this line) and
+    /// runs up to a line equal to `delimiter`. With `strip_tabs` leading tabs
+    /// are removed from every line, as for `<<-`.
+    pub fn read_heredoc(
+        &mut self,
+        delimiter: &str,
+        strip_tabs: bool,
+    ) -> Result<String, ParseError> {
+        let start = match self.heredoc_end {
+            Some(end) => end,
+            None => {
+                let newline = self.chars[self.pos..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .ok_or_else(|| ParseError::Incomplete("here-document".into()))?;
+                self.pos + newline + 1
+            }
+        };
+
+        let mut body = String::new();
+        let mut pos = start;
+        loop {
+            if pos >= self.chars.len() {
+                return Err(ParseError::Incomplete(format!(
+                    "here-document delimited by `{}'",
+                    delimiter
+                )));
+            }
+
+            let len = self.chars[pos..]
+                .iter()
+                .position(|&c| c == '\n')
+                .unwrap_or(self.chars.len() - pos);
+            let line: String = self.chars[pos..pos + len].iter().collect();
+            pos = (pos + len + 1).min(self.chars.len());
+
+            let line = if strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line.as_str()
+            };
+            if line == delimiter {
+                break;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+
+        self.heredoc_end = Some(pos);
+        Ok(body)
+    }
+}
+
+/// Splits an unquoted here-document body into word parts
+pub fn parse_heredoc_body(body: &str) -> Result<Vec<WordPart>, ParseError> {
+    let chars: Vec<char> = body.chars().collect();
+    Lexer::new(&chars).read_quoted_parts(None)
+}
+
+fn flush_literal(literal: &mut String, parts: &mut Vec<WordPart>) {
+    if !literal.is_empty() {
+        parts.push(WordPart::Literal(std::mem::take(literal)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<Token> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut lexer = Lexer::new(&chars);
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.next_token().unwrap() {
+                Token::Eof => return tokens,
+                token => tokens.push(token),
+            }
+        }
+    }
+
+    fn word(parts: Vec<WordPart>) -> Token {
+        Token::Word(Word { parts })
+    }
+
+    fn literal(text: &str) -> WordPart {
+        WordPart::Literal(text.to_string())
+    }
+
+    fn param(name: &str) -> WordPart {
+        WordPart::Param(ParamExpansion {
+            name: name.to_string(),
+            op: None,
+        })
+    }
+
+    #[test]
+    fn splits_words_and_operators() {
+        assert_eq!(
+            tokens("a|b && c;d\n"),
+            vec![
+                word(vec![literal("a")]),
+                Token::Op(Operator::Pipe),
+                word(vec![literal("b")]),
+                Token::Op(Operator::AndIf),
+                word(vec![literal("c")]),
+                Token::Op(Operator::Semi),
+                word(vec![literal("d")]),
+                Token::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_quoting() {
+        assert_eq!(
+            tokens(r#"'a b'"c $x"\ d"#),
+            vec![word(vec![
+                WordPart::Quoted("a b".to_string()),
+                WordPart::DoubleQuoted(vec![literal("c "), param("x")]),
+                WordPart::Quoted(" ".to_string()),
+                literal("d"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn reads_io_numbers_only_before_redirections() {
+        assert_eq!(
+            tokens("2>err 2"),
+            vec![
+                Token::IoNumber(2),
+                Token::Op(Operator::Great),
+                word(vec![literal("err")]),
+                word(vec![literal("2")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_parameter_expansions() {
+        assert_eq!(tokens("$1$?"), vec![word(vec![param("1"), param("?")])]);
+        assert_eq!(
+            tokens("${x:-y}"),
+            vec![word(vec![WordPart::Param(ParamExpansion {
+                name: "x".to_string(),
+                op: Some(ParamOp::Default {
+                    colon: true,
+                    word: Word {
+                        parts: vec![literal("y")]
+                    },
+                }),
+            })])]
+        );
+        let chars: Vec<char> = "${x!}".chars().collect();
+        assert_eq!(
+            Lexer::new(&chars).next_token(),
+            Err(ParseError::BadSubstitution("${x!}".to_string()))
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        assert_eq!(
+            tokens("a # b c\nd#e"),
+            vec![
+                word(vec![literal("a")]),
+                Token::Newline,
+                word(vec![literal("d#e")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_quotes_as_incomplete() {
+        for input in ["'a", "\"a", "$(a", "${a"] {
+            let chars: Vec<char> = input.chars().collect();
+            let error = Lexer::new(&chars).next_token().unwrap_err();
+            assert!(error.is_incomplete(), "{}: {:?}", input, error);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+// syntax/mod.rs
+
+mod ast;
+mod display;
+mod lexer;
+mod parser;
+
+pub use ast::*;
+pub use lexer::{Lexer, Operator, ParseError, Token};
+pub use parser::{Parser, is_reserved, parse, parse_with_aliases};
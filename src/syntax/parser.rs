@@ -0,0 +1,703 @@
+// syntax/parser.rs
+
+use crate::syntax::ast::{
+    AndOr, AndOrOp, CaseArm, Command, CompoundCommand, List, ListItem, Pipeline, Redirect,
+    RedirectOp, SimpleCommand, Word, WordPart, is_name,
+};
+use crate::syntax::lexer::{Lexer, Operator, ParseError, Token, parse_heredoc_body};
+use std::collections::HashMap;
+
+/// Reserved words that end the list before them
+const TERMINATORS: &[&str] = &["then", "elif", "else", "fi", "do", "done", "esac", "}"];
+
+/// Parses a complete command line into a [`List`]
+pub fn parse(input: &str) -> Result<List, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut parser = Parser::new(Lexer::new(&chars));
+    parser.parse_program()
+}
+
+/// Like [`parse`], but replaces aliases in command position by their values
+pub fn parse_with_aliases(
+    input: &str,
+    aliases: &HashMap<String, String>,
+) -> Result<List, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut parser = Parser::new(Lexer::new(&chars).with_aliases(aliases));
+    parser.parse_program()
+}
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    /// Tokens to return before reading on, in reverse order: the peeked
+    /// token and the tokens of an expanded alias
+    pending: Vec<Token>,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(lexer: Lexer<'a>) -> Self {
+        Self {
+            lexer,
+            pending: Vec::new(),
+        }
+    }
+
+    fn peek(&mut self) -> Result<&Token, ParseError> {
+        if self.pending.is_empty() {
+            let token = self.lexer.next_token()?;
+            self.pending.push(token);
+        }
+        Ok(self.pending.last().unwrap())
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        match self.pending.pop() {
+            Some(token) => Ok(token),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    /// Replaces an alias in command position by the tokens of its value.
+    /// The value is checked for aliases again, but an alias is never
+    /// expanded inside its own expansion.
+    fn expand_aliases(&mut self) -> Result<(), ParseError> {
+        let Some(aliases) = self.lexer.aliases() else {
+            return Ok(());
+        };
+        let mut expanded: Vec<String> = Vec::new();
+        loop {
+            let name = match self.peek()? {
+                Token::Word(word) => match word.as_literal() {
+                    Some(name) if !is_reserved(name) => name.to_string(),
+                    _ => return Ok(()),
+                },
+                _ => return Ok(()),
+            };
+            let Some(value) = aliases.get(&name).filter(|_| !expanded.contains(&name)) else {
+                return Ok(());
+            };
+            self.next()?;
+
+            let chars: Vec<char> = value.chars().collect();
+            let mut lexer = Lexer::new(&chars);
+            let mut tokens = Vec::new();
+            loop {
+                match lexer.next_token()? {
+                    Token::Eof => break,
+                    token => tokens.push(token),
+                }
+            }
+            self.pending.extend(tokens.into_iter().rev());
+            expanded.push(name);
+        }
+    }
+
+    fn unexpected(token: &Token) -> ParseError {
+        match token {
+            Token::Eof => ParseError::Incomplete("expected a command".into()),
+            _ => ParseError::Unexpected(token.to_string()),
+        }
+    }
+
+    fn skip_newlines(&mut self) -> Result<(), ParseError> {
+        while *self.peek()? == Token::Newline {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    pub fn parse_program(&mut self) -> Result<List, ParseError> {
+        let list = self.parse_list()?;
+        match self.next()? {
+            Token::Eof => Ok(list),
+            token => Err(ParseError::Unexpected(token.to_string())),
+        }
+    }
+
+    /// Parses the commands of a `$(...)` substitution up to and including
+    /// the closing parenthesis
+    pub fn parse_substitution(&mut self) -> Result<List, ParseError> {
+        let list = self.parse_list()?;
+        match self.next()? {
+            Token::Op(Operator::RParen) => Ok(list),
+            Token::Eof => Err(ParseError::Incomplete("command substitution".into())),
+            token => Err(ParseError::Unexpected(token.to_string())),
+        }
+    }
+
+    /// Returns the lexer, positioned after the last token that was consumed
+    pub fn into_lexer(self) -> Lexer<'a> {
+        self.lexer
+    }
+
+    fn starts_command(token: &Token) -> bool {
+        match token {
+            Token::Word(word) => !word
+                .as_literal()
+                .is_some_and(|word| TERMINATORS.contains(&word)),
+            Token::Op(Operator::LParen) => true,
+            token => Self::starts_redirect(token),
+        }
+    }
+
+    fn starts_redirect(token: &Token) -> bool {
+        match token {
+            Token::IoNumber(_) => true,
+            Token::Op(op) => redirect_op(*op).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether the next token is the reserved word `word`
+    fn at_keyword(&mut self, word: &str) -> Result<bool, ParseError> {
+        Ok(matches!(self.peek()?, Token::Word(w) if w.as_literal() == Some(word)))
+    }
+
+    fn expect_keyword(&mut self, word: &str) -> Result<(), ParseError> {
+        match self.next()? {
+            Token::Word(w) if w.as_literal() == Some(word) => Ok(()),
+            Token::Eof => Err(ParseError::Incomplete(format!("expected `{}'", word))),
+            token => Err(ParseError::Unexpected(token.to_string())),
+        }
+    }
+
+    fn expect_op(&mut self, op: Operator) -> Result<(), ParseError> {
+        match self.next()? {
+            Token::Op(found) if found == op => Ok(()),
+            token => Err(Self::unexpected(&token)),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<List, ParseError> {
+        let mut list = List::default();
+        self.skip_newlines()?;
+
+        while Self::starts_command(self.peek()?) {
+            let and_or = self.parse_and_or()?;
+            let background = match self.peek()? {
+                Token::Op(Operator::Semi) | Token::Newline => {
+                    self.next()?;
+                    false
+                }
+                Token::Op(Operator::Amp) => {
+                    self.next()?;
+                    true
+                }
+                _ => {
+                    list.items.push(ListItem {
+                        and_or,
+                        background: false,
+                    });
+                    break;
+                }
+            };
+            list.items.push(ListItem { and_or, background });
+            self.skip_newlines()?;
+        }
+
+        Ok(list)
+    }
+
+    fn parse_and_or(&mut self) -> Result<AndOr, ParseError> {
+        let first = self.parse_pipeline()?;
+        let mut rest = Vec::new();
+
+        loop {
+            let op = match self.peek()? {
+                Token::Op(Operator::AndIf) => AndOrOp::And,
+                Token::Op(Operator::OrIf) => AndOrOp::Or,
+                _ => break,
+            };
+            self.next()?;
+            self.skip_newlines()?;
+            rest.push((op, self.parse_pipeline()?));
+        }
+
+        Ok(AndOr { first, rest })
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline, ParseError> {
+        let negated = matches!(self.peek()?, Token::Word(w) if w.as_literal() == Some("!"));
+        if negated {
+            self.next()?;
+        }
+
+        let mut commands = vec![self.parse_command()?];
+        while *self.peek()? == Token::Op(Operator::Pipe) {
+            self.next()?;
+            self.skip_newlines()?;
+            commands.push(self.parse_command()?);
+        }
+
+        Ok(Pipeline { negated, commands })
+    }
+
+    fn parse_command(&mut self) -> Result<Command, ParseError> {
+        self.expand_aliases()?;
+        let keyword = match self.peek()? {
+            Token::Word(word) => word.as_literal().map(str::to_string),
+            _ => None,
+        };
+        let compound = match keyword.as_deref() {
+            _ if *self.peek()? == Token::Op(Operator::LParen) => self.parse_subshell()?,
+            Some("{") => self.parse_group()?,
+            Some("if") => self.parse_if()?,
+            Some("while") => self.parse_while(false)?,
+            Some("until") => self.parse_while(true)?,
+            Some("for") => self.parse_for()?,
+            Some("case") => self.parse_case()?,
+            Some("function") => return self.parse_function(),
+            _ => return self.parse_simple_command(),
+        };
+
+        let mut redirects = Vec::new();
+        while Self::starts_redirect(self.peek()?) {
+            redirects.push(self.parse_redirect()?);
+        }
+        Ok(Command::Compound(compound, redirects))
+    }
+
+    /// Whether a token starts a compound command, as needed for a function
+    /// body
+    fn starts_compound(token: &Token) -> bool {
+        match token {
+            Token::Op(Operator::LParen) => true,
+            Token::Word(word) => matches!(
+                word.as_literal(),
+                Some("{" | "if" | "while" | "until" | "for" | "case")
+            ),
+            _ => false,
+        }
+    }
+
+    /// `function name [()] body`
+    fn parse_function(&mut self) -> Result<Command, ParseError> {
+        self.next()?;
+        let name = match self.next()? {
+            Token::Word(word) => function_name(&word)?,
+            token => return Err(Self::unexpected(&token)),
+        };
+        if *self.peek()? == Token::Op(Operator::LParen) {
+            self.next()?;
+            self.expect_op(Operator::RParen)?;
+        }
+        self.parse_function_body(name)
+    }
+
+    fn parse_function_body(&mut self, name: String) -> Result<Command, ParseError> {
+        self.skip_newlines()?;
+        if !Self::starts_compound(self.peek()?) {
+            let token = self.next()?;
+            return Err(Self::unexpected(&token));
+        }
+        let body = Box::new(self.parse_command()?);
+        Ok(Command::FunctionDef { name, body })
+    }
+
+    /// A list that must contain at least one command, such as a loop body
+    fn parse_body(&mut self) -> Result<List, ParseError> {
+        let list = self.parse_list()?;
+        if list.items.is_empty() {
+            let token = self.next()?;
+            return Err(Self::unexpected(&token));
+        }
+        Ok(list)
+    }
+
+    fn parse_subshell(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.next()?;
+        let body = self.parse_body()?;
+        match self.next()? {
+            Token::Op(Operator::RParen) => Ok(CompoundCommand::Subshell(body)),
+            Token::Eof => Err(ParseError::Incomplete("expected `)'".into())),
+            token => Err(ParseError::Unexpected(token.to_string())),
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.next()?;
+        let body = self.parse_body()?;
+        self.expect_keyword("}")?;
+        Ok(CompoundCommand::Group(body))
+    }
+
+    fn parse_if(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.next()?;
+        let mut branches = Vec::new();
+        let mut else_branch = None;
+
+        loop {
+            let condition = self.parse_body()?;
+            self.expect_keyword("then")?;
+            branches.push((condition, self.parse_body()?));
+
+            match self.next()? {
+                Token::Word(w) if w.as_literal() == Some("elif") => continue,
+                Token::Word(w) if w.as_literal() == Some("else") => {
+                    else_branch = Some(self.parse_body()?);
+                    self.expect_keyword("fi")?;
+                    break;
+                }
+                Token::Word(w) if w.as_literal() == Some("fi") => break,
+                Token::Eof => return Err(ParseError::Incomplete("expected `fi'".into())),
+                token => return Err(ParseError::Unexpected(token.to_string())),
+            }
+        }
+
+        Ok(CompoundCommand::If {
+            branches,
+            else_branch,
+        })
+    }
+
+    fn parse_while(&mut self, until: bool) -> Result<CompoundCommand, ParseError> {
+        self.next()?;
+        let condition = self.parse_body()?;
+        let body = self.parse_do_group()?;
+        Ok(CompoundCommand::While {
+            condition,
+            body,
+            until,
+        })
+    }
+
+    /// `do list done`
+    fn parse_do_group(&mut self) -> Result<List, ParseError> {
+        self.expect_keyword("do")?;
+        let body = self.parse_body()?;
+        self.expect_keyword("done")?;
+        Ok(body)
+    }
+
+    fn parse_for(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.next()?;
+        let name = match self.next()? {
+            Token::Word(word) if word.as_literal().is_some_and(is_name) => word.unquoted_text(),
+            token => return Err(Self::unexpected(&token)),
+        };
+
+        self.skip_newlines()?;
+        let words = if self.at_keyword("in")? {
+            self.next()?;
+            let mut words = Vec::new();
+            while let Token::Word(_) = self.peek()? {
+                if let Token::Word(word) = self.next()? {
+                    words.push(word);
+                }
+            }
+            match self.next()? {
+                Token::Op(Operator::Semi) | Token::Newline => {}
+                token => return Err(Self::unexpected(&token)),
+            }
+            Some(words)
+        } else {
+            if *self.peek()? == Token::Op(Operator::Semi) {
+                self.next()?;
+            }
+            None
+        };
+
+        self.skip_newlines()?;
+        let body = self.parse_do_group()?;
+        Ok(CompoundCommand::For { name, words, body })
+    }
+
+    fn parse_case(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.next()?;
+        let word = match self.next()? {
+            Token::Word(word) => word,
+            token => return Err(Self::unexpected(&token)),
+        };
+        self.skip_newlines()?;
+        self.expect_keyword("in")?;
+        self.skip_newlines()?;
+
+        let mut arms = Vec::new();
+        while !self.at_keyword("esac")? {
+            if *self.peek()? == Token::Op(Operator::LParen) {
+                self.next()?;
+            }
+
+            let mut patterns = Vec::new();
+            loop {
+                match self.next()? {
+                    Token::Word(pattern) => patterns.push(pattern),
+                    token => return Err(Self::unexpected(&token)),
+                }
+                if *self.peek()? != Token::Op(Operator::Pipe) {
+                    break;
+                }
+                self.next()?;
+            }
+            self.expect_op(Operator::RParen)?;
+
+            let body = self.parse_list()?;
+            arms.push(CaseArm { patterns, body });
+
+            match self.peek()? {
+                Token::Op(Operator::DSemi) => {
+                    self.next()?;
+                    self.skip_newlines()?;
+                }
+                Token::Word(w) if w.as_literal() == Some("esac") => {}
+                _ => {
+                    let token = self.next()?;
+                    return Err(Self::unexpected(&token));
+                }
+            }
+        }
+        self.next()?;
+
+        Ok(CompoundCommand::Case { word, arms })
+    }
+
+    fn parse_simple_command(&mut self) -> Result<Command, ParseError> {
+        let mut command = SimpleCommand::default();
+
+        loop {
+            let token = self.peek()?;
+            if let Token::Word(_) = token {
+                if let Token::Word(word) = self.next()? {
+                    match word.as_assignment() {
+                        Some(assignment) if command.words.is_empty() => {
+                            command.assignments.push(assignment)
+                        }
+                        _ => command.words.push(word),
+                    }
+                }
+                // `name()` starts a function definition
+                if let [name] = command.words.as_slice()
+                    && command.assignments.is_empty()
+                    && command.redirects.is_empty()
+                    && *self.peek()? == Token::Op(Operator::LParen)
+                {
+                    let name = function_name(name)?;
+                    self.next()?;
+                    self.expect_op(Operator::RParen)?;
+                    return self.parse_function_body(name);
+                }
+            } else if Self::starts_command(token) {
+                command.redirects.push(self.parse_redirect()?);
+            } else {
+                break;
+            }
+        }
+
+        if command.words.is_empty()
+            && command.redirects.is_empty()
+            && command.assignments.is_empty()
+        {
+            let token = self.next()?;
+            return Err(Self::unexpected(&token));
+        }
+
+        Ok(Command::Simple(command))
+    }
+
+    fn parse_redirect(&mut self) -> Result<Redirect, ParseError> {
+        let fd = match self.peek()? {
+            Token::IoNumber(n) => {
+                let n = *n;
+                self.next()?;
+                Some(n)
+            }
+            _ => None,
+        };
+
+        let token = self.next()?;
+        let op = match &token {
+            Token::Op(op) => redirect_op(*op),
+            _ => None,
+        }
+        .ok_or_else(|| Self::unexpected(&token))?;
+
+        let target = match self.next()? {
+            Token::Word(target) => target,
+            token => return Err(Self::unexpected(&token)),
+        };
+
+        if op != RedirectOp::HereDoc {
+            return Ok(Redirect { fd, op, target });
+        }
+
+        // The delimiter is taken literally; quoting any part of it disables
+        // expansion inside the body
+        let strip_tabs = token == Token::Op(Operator::DLessDash);
+        let body = self
+            .lexer
+            .read_heredoc(&target.unquoted_text(), strip_tabs)?;
+        let target = if target.is_quoted() {
+            Word::quoted(body)
+        } else {
+            Word {
+                parts: vec![WordPart::DoubleQuoted(parse_heredoc_body(&body)?)],
+            }
+        };
+
+        Ok(Redirect { fd, op, target })
+    }
+}
+
+/// Whether a word is reserved where a command name is expected
+pub fn is_reserved(word: &str) -> bool {
+    TERMINATORS.contains(&word)
+        || matches!(
+            word,
+            "!" | "{" | "if" | "while" | "until" | "for" | "case" | "function"
+        )
+}
+
+/// Checks that a word can name a function. Any unquoted word will do
+/// except for reserved words and names containing `/` or `=`.
+fn function_name(word: &Word) -> Result<String, ParseError> {
+    match word.as_literal() {
+        Some(name) if !is_reserved(name) && !name.contains(['/', '=']) => Ok(name.to_string()),
+        _ => Err(ParseError::Unexpected(word.to_string())),
+    }
+}
+
+fn redirect_op(op: Operator) -> Option<RedirectOp> {
+    Some(match op {
+        Operator::Less => RedirectOp::Input,
+        Operator::Great => RedirectOp::Output,
+        Operator::Clobber => RedirectOp::Clobber,
+        Operator::DGreat => RedirectOp::Append,
+        Operator::LessGreat => RedirectOp::ReadWrite,
+        Operator::LessAnd => RedirectOp::DupInput,
+        Operator::GreatAnd => RedirectOp::DupOutput,
+        Operator::AndGreat => RedirectOp::OutputAll,
+        Operator::AndDGreat => RedirectOp::AppendAll,
+        Operator::DLess | Operator::DLessDash => RedirectOp::HereDoc,
+        Operator::TLess => RedirectOp::HereString,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(text: &str) -> Word {
+        Word {
+            parts: vec![WordPart::Literal(text.to_string())],
+        }
+    }
+
+    /// The first command of a line, which must be a simple one
+    fn simple(input: &str) -> SimpleCommand {
+        let list = parse(input).unwrap();
+        match &list.items[0].and_or.first.commands[0] {
+            Command::Simple(command) => command.clone(),
+            command => panic!("not a simple command: {:?}", command),
+        }
+    }
+
+    #[test]
+    fn parses_lists_and_pipelines() {
+        let list = parse("a | b && ! c; d &").unwrap();
+        assert_eq!(list.items.len(), 2);
+        let first = &list.items[0];
+        assert!(!first.background);
+        assert_eq!(first.and_or.first.commands.len(), 2);
+        let (op, pipeline) = &first.and_or.rest[0];
+        assert_eq!(*op, AndOrOp::And);
+        assert!(pipeline.negated);
+        assert!(list.items[1].background);
+    }
+
+    #[test]
+    fn separates_assignments_words_and_redirects() {
+        let command = simple("A=1 cmd B=2 2>>log <in");
+        assert_eq!(command.assignments.len(), 1);
+        assert_eq!(command.assignments[0].name, "A");
+        assert_eq!(command.words, vec![literal("cmd"), literal("B=2")]);
+        assert_eq!(
+            command.redirects,
+            vec![
+                Redirect {
+                    fd: Some(2),
+                    op: RedirectOp::Append,
+                    target: literal("log"),
+                },
+                Redirect {
+                    fd: None,
+                    op: RedirectOp::Input,
+                    target: literal("in"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_heredoc_bodies() {
+        let command = simple("cat <<-EOF\n\tline\n\tEOF\n");
+        assert_eq!(command.redirects[0].op, RedirectOp::HereDoc);
+        assert_eq!(command.redirects[0].target.unquoted_text(), "line\n");
+    }
+
+    #[test]
+    fn parses_compound_commands() {
+        let list = parse("if a; then b; elif c; then d; else e; fi").unwrap();
+        let Command::Compound(
+            CompoundCommand::If {
+                branches,
+                else_branch,
+            },
+            _,
+        ) = &list.items[0].and_or.first.commands[0]
+        else {
+            panic!("not an if: {:?}", list);
+        };
+        assert_eq!(branches.len(), 2);
+        assert!(else_branch.is_some());
+
+        let list = parse("for x in 1 2; do echo $x; done >out").unwrap();
+        let Command::Compound(CompoundCommand::For { name, words, .. }, redirects) =
+            &list.items[0].and_or.first.commands[0]
+        else {
+            panic!("not a for loop: {:?}", list);
+        };
+        assert_eq!(name, "x");
+        assert_eq!(words.as_ref().map(Vec::len), Some(2));
+        assert_eq!(redirects.len(), 1);
+    }
+
+    #[test]
+    fn parses_function_definitions() {
+        for input in ["f() { a; }", "function f { a; }"] {
+            let list = parse(input).unwrap();
+            let Command::FunctionDef { name, body } = &list.items[0].and_or.first.commands[0]
+            else {
+                panic!("not a function: {:?}", list);
+            };
+            assert_eq!(name, "f");
+            assert!(matches!(
+                **body,
+                Command::Compound(CompoundCommand::Group(_), _)
+            ));
+        }
+    }
+
+    #[test]
+    fn replaces_aliases_in_command_position() {
+        let aliases = HashMap::from([("ll".to_string(), "ls -l".to_string())]);
+        let list = parse_with_aliases("ll ll", &aliases).unwrap();
+        let Command::Simple(command) = &list.items[0].and_or.first.commands[0] else {
+            panic!("not a simple command: {:?}", list);
+        };
+        assert_eq!(
+            command.words,
+            vec![literal("ls"), literal("-l"), literal("ll")]
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        assert_eq!(parse("a | ;"), Err(ParseError::Unexpected(";".to_string())));
+        assert_eq!(parse("fi"), Err(ParseError::Unexpected("fi".to_string())));
+        for input in ["if a; then b", "a &&", "(a", "case x in"] {
+            let error = parse(input).unwrap_err();
+            assert!(error.is_incomplete(), "{}: {:?}", input, error);
+        }
+    }
+}
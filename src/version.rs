@@ -1,5 +1,6 @@
 // version.rs
 
+use crate::error::NeocashError;
 use reqwest;
 use serde::Deserialize;
 
@@ -18,7 +19,7 @@ struct AurPackageInfo {
     pub version: String,
 }
 
-fn fetch_aur_version() -> Result<String, String> {
+fn fetch_aur_version() -> Result<String, NeocashError> {
     let client = reqwest::blocking::Client::new();
     let pkg_name = "neocash"; // Убедитесь, что это точное имя вашего пакета на AUR
     let url = format!("https://aur.archlinux.org/rpc/v5/info/{}", pkg_name);
@@ -26,26 +27,30 @@ fn fetch_aur_version() -> Result<String, String> {
     let response = client
         .get(&url)
         .send()
-        .map_err(|e| format!("Failed to send request to AUR API: {}", e))?
+        .map_err(|source| network("Failed to send request to AUR API", source))?
         .error_for_status()
-        .map_err(|e| format!("AUR API returned an error: {}", e))?
+        .map_err(|source| network("AUR API returned an error", source))?
         .json::<AurRpcResponse>()
-        .map_err(|e| format!("Failed to parse AUR API response: {}", e))?;
+        .map_err(|source| network("Failed to parse AUR API response", source))?;
 
     // Check if we have at least one result
     if let Some(package_info) = response.results.into_iter().next() {
         Ok(package_info.version)
     } else {
-        Err(format!("Package '{}' not found in AUR", pkg_name))
+        Err(format!("Package '{}' not found in AUR", pkg_name).into())
     }
 }
 
+fn network(context: &'static str, source: reqwest::Error) -> NeocashError {
+    NeocashError::Network { context, source }
+}
+
 // Function to extract the base version from a version string
 pub fn get_base_version_str(ver_str: &str) -> String {
-    ver_str.splitn(2, '-').next().unwrap_or(ver_str).to_string()
+    ver_str.split('-').next().unwrap_or(ver_str).to_string()
 }
 
-pub fn check_for_updates() -> Result<String, String> {
+pub fn check_for_updates() -> Result<String, NeocashError> {
     let current_version = env!("CARGO_PKG_VERSION");
 
     let base_current_version = get_base_version_str(current_version);
@@ -54,20 +59,19 @@ pub fn check_for_updates() -> Result<String, String> {
 
     let cache_file = cache_dir.join("neocash/version_cache");
 
-    if let Ok(cached) = std::fs::read_to_string(&cache_file) {
-        if let Some((timestamp, full_cached_version)) = cached.split_once('|') {
-            if let Ok(ts) = timestamp.parse::<u64>() {
-                let now = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map_err(|e| e.to_string())?
-                    .as_secs();
-
-                // Check if the cached version is still valid
-                let base_cached_version = get_base_version_str(full_cached_version);
-                if now - ts < 3600 && base_cached_version == base_current_version {
-                    return Ok(full_cached_version.to_string()); // Return the full version from cache
-                }
-            }
+    if let Ok(cached) = std::fs::read_to_string(&cache_file)
+        && let Some((timestamp, full_cached_version)) = cached.split_once('|')
+        && let Ok(ts) = timestamp.parse::<u64>()
+    {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+
+        // Check if the cached version is still valid
+        let base_cached_version = get_base_version_str(full_cached_version);
+        if now - ts < 3600 && base_cached_version == base_current_version {
+            return Ok(full_cached_version.to_string()); // Return the full version from cache
         }
     }
 
@@ -76,7 +80,7 @@ pub fn check_for_updates() -> Result<String, String> {
 
     // Update the cache file with the full version from AUR
     std::fs::create_dir_all(cache_file.parent().unwrap())
-        .map_err(|e| format!("Failed to create cache dir: {}", e))?;
+        .map_err(|e| NeocashError::io("Failed to create cache dir", e))?;
 
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -84,7 +88,7 @@ pub fn check_for_updates() -> Result<String, String> {
         .as_secs();
 
     std::fs::write(&cache_file, format!("{}|{}", now, aur_ver))
-        .map_err(|e| format!("Failed to write cache: {}", e))?;
+        .map_err(|e| NeocashError::io("Failed to write cache", e))?;
 
     Ok(aur_ver) // Return the full version from AUR
 }
@@ -0,0 +1,32 @@
+// tests/cli.rs
+
+use std::process::{Command, Output};
+
+/// Runs the shell binary with `args`, away from the configuration of the
+/// user running the tests
+fn neocash(args: &[&str]) -> Output {
+    let config = std::env::temp_dir().join(format!("neocash-cli-{}", std::process::id()));
+    Command::new(env!("CARGO_BIN_EXE_neocash"))
+        .args(args)
+        .env("XDG_CONFIG_HOME", &config)
+        .env("HOME", &config)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn exit_with_a_bad_argument_fails_with_2() {
+    let output = neocash(&["-c", "exit abc; echo after"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(output.stdout, b"");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("abc: numeric argument required"),
+        "{}",
+        stderr
+    );
+
+    let script = "\"$1\" -c 'exit abc' 2>/dev/null; echo $?";
+    let output = neocash(&["-c", script, "neocash", env!("CARGO_BIN_EXE_neocash")]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n");
+}
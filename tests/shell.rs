@@ -0,0 +1,177 @@
+// tests/shell.rs
+
+use neocash::config::ShellConfig;
+use neocash::exec::{ShellState, Variables};
+use neocash::{Output, Shell};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+/// Shells fork for pipelines and `$(...)`, which is only safe while no
+/// other test thread is in the middle of the same
+static FORK: Mutex<()> = Mutex::new(());
+
+fn serial() -> MutexGuard<'static, ()> {
+    FORK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A directory of its own for each test
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neocash-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn shell_in(dir: PathBuf) -> Shell {
+    Shell::with_state(
+        ShellConfig::default(),
+        ShellState::new(dir, Variables::from_env()),
+    )
+}
+
+fn shell() -> Shell {
+    shell_in(std::env::temp_dir())
+}
+
+#[test]
+fn captures_stdout_and_stderr() {
+    let _guard = serial();
+    let result = shell().eval("echo hello; echo oops >&2").unwrap();
+    assert_eq!(result.stdout, "hello\n");
+    assert_eq!(result.stderr, "oops\n");
+    assert!(result.success());
+}
+
+#[test]
+fn captures_output_of_pipelines_and_external_commands() {
+    let _guard = serial();
+    let result = shell()
+        .eval("echo abc | tr a-z A-Z; ls /nonexistent")
+        .unwrap();
+    assert_eq!(result.stdout, "ABC\n");
+    assert!(result.stderr.contains("/nonexistent"));
+    assert_ne!(result.status, 0);
+}
+
+#[test]
+fn command_substitution_errors_go_to_the_captured_stderr() {
+    let _guard = serial();
+    let result = shell()
+        .eval("x=$(echo out; ls /nonexistent); echo \"[$x]\"")
+        .unwrap();
+    assert_eq!(result.stdout, "[out]\n");
+    assert!(result.stderr.contains("/nonexistent"));
+}
+
+#[test]
+fn state_persists_across_calls() {
+    let _guard = serial();
+    let dir = scratch_dir("state");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    let mut shell = shell_in(dir.clone());
+
+    shell
+        .eval("greeting=hi; greet() { echo \"$greeting $1\"; }; alias say=echo; cd sub")
+        .unwrap();
+    let result = shell.eval("greet there\nsay $PWD").unwrap();
+    assert_eq!(
+        result.stdout,
+        format!("hi there\n{}\n", dir.join("sub").display())
+    );
+    assert_eq!(shell.state().cwd, dir.join("sub"));
+    assert_eq!(shell.state().vars.get("greeting"), Some("hi"));
+}
+
+#[test]
+fn reports_exit_status() {
+    let _guard = serial();
+    let mut shell = shell();
+    assert_eq!(shell.eval("true").unwrap().status, 0);
+    assert_eq!(shell.eval("false").unwrap().status, 1);
+    assert_eq!(shell.eval("(exit 3)").unwrap().status, 3);
+    assert_eq!(shell.eval("echo $?").unwrap().stdout, "3\n");
+
+    let result = shell.eval("no-such-command-here").unwrap();
+    assert_eq!(result.status, 127);
+    assert!(result.stderr.contains("command not found"));
+    assert_eq!(shell.eval("echo '").unwrap().status, 2);
+}
+
+#[test]
+fn exit_runs_the_exit_trap_and_stops_the_shell() {
+    let _guard = serial();
+    let mut shell = shell();
+    let result = shell
+        .eval("trap 'echo bye' EXIT\necho before\nexit 4\necho after")
+        .unwrap();
+    assert_eq!(result.status, 4);
+    assert_eq!(result.stdout, "before\nbye\n");
+    assert!(shell.has_exited());
+
+    let result = shell.eval("echo again").unwrap();
+    assert_eq!(result.status, 4);
+    assert_eq!(result.stdout, "");
+}
+
+#[test]
+fn errexit_exits_like_exit() {
+    let _guard = serial();
+    let mut shell = shell();
+    let result = shell.eval("set -e; echo one; false; echo two").unwrap();
+    assert_eq!(result.status, 1);
+    assert_eq!(result.stdout, "one\n");
+    assert!(shell.has_exited());
+}
+
+#[test]
+fn shells_keep_their_own_working_directory() {
+    let _guard = serial();
+    let first = scratch_dir("first");
+    let second = scratch_dir("second");
+    std::fs::write(first.join("marker"), "").unwrap();
+    let mut a = shell_in(first.clone());
+    let mut b = shell_in(second);
+
+    assert_eq!(a.eval("ls").unwrap().stdout, "marker\n");
+    assert_eq!(b.eval("ls").unwrap().stdout, "");
+    let relative = format!(
+        "cat ../{}/marker; echo $?",
+        first.file_name().unwrap().display()
+    );
+    assert_eq!(b.eval(&relative).unwrap().stdout, "0\n");
+    assert_eq!(a.state().cwd, first);
+}
+
+#[test]
+fn streaming_leaves_the_result_empty() {
+    let _guard = serial();
+    let mut shell = shell().with_output(Output::Stream);
+    let result = shell
+        .eval("x=$(echo captured); test \"$x\" = captured")
+        .unwrap();
+    assert!(result.success());
+    assert_eq!(result.stdout, "");
+}
+
+#[test]
+fn runs_again_after_an_interrupted_command() {
+    let _guard = serial();
+    let mut shell = shell();
+    let result = shell.eval("sh -c 'kill -INT $$'; echo skipped").unwrap();
+    assert_eq!(result.status, 130);
+    assert_eq!(result.stdout, "");
+
+    let result = shell.eval("echo hi").unwrap();
+    assert_eq!(result.status, 0);
+    assert_eq!(result.stdout, "hi\n");
+}
+
+#[test]
+fn trap_output_is_captured() {
+    let _guard = serial();
+    let mut shell = shell();
+    let result = shell
+        .eval("trap 'echo failed $?; echo oops >&2' ERR\nfalse\necho done")
+        .unwrap();
+    assert_eq!(result.stdout, "failed 1\ndone\n");
+    assert_eq!(result.stderr, "oops\n");
+}